@@ -0,0 +1,121 @@
+//! Sprite sheets: one RGBA image holding many sprites, sliced apart by region
+
+use std::error::Error;
+use std::fmt;
+
+/// Bytes per RGBA pixel
+const PIXEL: usize = 4;
+
+/// A row-major RGBA image from which individual sprites or animation frames are cut out
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AtlasError {
+    /// The image data doesn't hold exactly `width * height` RGBA pixels
+    Length { width: u32, height: u32, len: usize },
+    /// The requested region doesn't lie entirely within the atlas
+    OutOfBounds {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Length { width, height, len } => write!(
+                f,
+                "a {}x{} atlas needs {} bytes of RGBA data, but got {}",
+                width,
+                height,
+                *width as usize * *height as usize * PIXEL,
+                len
+            ),
+            AtlasError::OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "region {}x{} at ({}, {}) extends outside the atlas",
+                width, height, x, y
+            ),
+        }
+    }
+}
+
+impl Error for AtlasError {}
+
+impl Atlas {
+    pub fn new(width: u32, height: u32, data: impl Into<Vec<u8>>) -> Result<Self, AtlasError> {
+        let data = data.into();
+        if data.len() != width as usize * height as usize * PIXEL {
+            return Err(AtlasError::Length {
+                width,
+                height,
+                len: data.len(),
+            });
+        }
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Copy the `w` by `h` region whose top-left corner is at (`x`, `y`) into a buffer of its
+    /// own, in the same row-major RGBA layout as a standalone sprite
+    pub fn sub(&self, x: u32, y: u32, w: u32, h: u32) -> Result<Vec<u8>, AtlasError> {
+        let mut data = Vec::with_capacity(w as usize * h as usize * PIXEL);
+        for row in self.rows(x, y, w, h)? {
+            data.extend_from_slice(row);
+        }
+        Ok(data)
+    }
+
+    /// Borrow the rows of the `w` by `h` region whose top-left corner is at (`x`, `y`), top to
+    /// bottom, without copying them
+    pub fn rows(
+        &self,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+    ) -> Result<impl Iterator<Item = &[u8]> + '_, AtlasError> {
+        let fits = |start: u32, len: u32, limit: u32| {
+            start.checked_add(len).is_some_and(|end| end <= limit)
+        };
+        if !fits(x, w, self.width) || !fits(y, h, self.height) {
+            return Err(AtlasError::OutOfBounds {
+                x,
+                y,
+                width: w,
+                height: h,
+            });
+        }
+
+        let stride = self.width as usize * PIXEL;
+        let start = x as usize * PIXEL;
+        let len = w as usize * PIXEL;
+        Ok((y..y + h).map(move |row| {
+            let offset = row as usize * stride + start;
+            &self.data[offset..offset + len]
+        }))
+    }
+}
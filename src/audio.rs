@@ -0,0 +1,96 @@
+//! Short synthesized sound effects, played through the Web Audio API
+//!
+//! Browsers won't start audio until the player has interacted with the page, so the
+//! `AudioContext` isn't made until [`Audio::unlock`] is called once input arrives. If it can't be
+//! made at all, every sound is silently skipped.
+
+use wasm_bindgen::JsValue;
+use web_sys::{console, AudioContext, OscillatorType};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Sound {
+    /// The ship fires
+    Shoot,
+    /// A member of the fleet is destroyed
+    Hit,
+    /// The ship loses a life
+    ShipDestroyed,
+    /// The ship catches a power-up
+    PowerUp,
+}
+
+impl Sound {
+    /// Waveform, starting and ending frequency in Hz, and length in seconds of the sound's sweep
+    fn tone(self) -> (OscillatorType, f32, f32, f64) {
+        match self {
+            Sound::Shoot => (OscillatorType::Square, 880.0, 660.0, 0.08),
+            Sound::Hit => (OscillatorType::Square, 220.0, 80.0, 0.12),
+            Sound::ShipDestroyed => (OscillatorType::Sawtooth, 440.0, 55.0, 0.6),
+            Sound::PowerUp => (OscillatorType::Triangle, 440.0, 1320.0, 0.15),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Audio {
+    enabled: bool,
+    context: Option<AudioContext>,
+}
+
+impl Audio {
+    const VOLUME: f32 = 0.15;
+
+    /// Sound effects that play once [`unlock`](Self::unlock)ed, or never if not `enabled`
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            context: None,
+        }
+    }
+
+    /// Set up audio output, which should wait until the player has pressed a key. Turns sound
+    /// off for good if the browser can't provide it.
+    pub(crate) fn unlock(&mut self) {
+        if !self.enabled || self.context.is_some() {
+            return;
+        }
+        match AudioContext::new() {
+            Ok(context) => self.context = Some(context),
+            Err(err) => {
+                console::log_1(&format!("Sound is off, no audio context: {:?}", err).into());
+                self.enabled = false;
+            }
+        }
+    }
+
+    pub(crate) fn play(&self, sound: Sound) {
+        let Some(context) = &self.context else {
+            return;
+        };
+        if let Err(err) = Self::sweep(context, sound) {
+            console::log_1(&format!("Failed to play {:?}: {:?}", sound, err).into());
+        }
+    }
+
+    /// Play `sound` as a single oscillator sliding between two pitches, fading out as it goes
+    fn sweep(context: &AudioContext, sound: Sound) -> Result<(), JsValue> {
+        let (waveform, start, end, length) = sound.tone();
+        let now = context.current_time();
+        let oscillator = context.create_oscillator()?;
+        oscillator.set_type(waveform);
+        oscillator.frequency().set_value_at_time(start, now)?;
+        oscillator
+            .frequency()
+            .exponential_ramp_to_value_at_time(end, now + length)?;
+        let gain = context.create_gain()?;
+        gain.gain().set_value_at_time(Self::VOLUME, now)?;
+        // Exponential ramps can't reach zero, but this is well below hearing
+        gain.gain()
+            .exponential_ramp_to_value_at_time(0.001, now + length)?;
+        oscillator.connect_with_audio_node(&gain)?;
+        gain.connect_with_audio_node(&context.destination())?;
+        oscillator.start()?;
+        oscillator.stop_with_when(now + length)?;
+        Ok(())
+    }
+}
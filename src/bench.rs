@@ -0,0 +1,104 @@
+//! Stress test for profiling, built only with the `benchmark` feature
+//!
+//! Build with `--features benchmark` and call `start_benchmark` from the page in place of
+//! `start_game`. The game runs with a 20x40 fleet and the ship firing every frame while weaving
+//! at random, then logs how long its frames took to compute.
+//!
+//! Adding the `direct-draw` feature as well draws sprites straight onto the page rather than
+//! through the backbuffer (see `graphics::Backbuffer`), so the two can be compared run for run.
+
+use std::{cell::RefCell, rc::Rc};
+
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::console;
+
+use crate::game::{Game, GameInput};
+use crate::graphics::TimeStamp;
+use crate::rng::Rng;
+
+const ROWS: usize = 20;
+const COLUMNS: usize = 40;
+/// Fixed seed, so every run sees the same movement
+const SEED: u64 = 0x5EED;
+/// Chance each frame that the ship changes direction
+const TURN_CHANCE: f64 = 0.05;
+
+/// Run `frames` frames of the stress test on the canvas with the id `canvas_id`, then log the
+/// minimum, average, and maximum time spent in each frame's update and draw
+#[wasm_bindgen]
+pub fn start_benchmark(canvas_id: &str, frames: u32) {
+    let window = web_sys::window().unwrap();
+    let performance = window.performance().unwrap();
+    let canvas = window
+        .document()
+        .unwrap()
+        .get_element_by_id(canvas_id)
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .map_err(|_| ())
+        .unwrap();
+    let mut game = match Game::new(&canvas, performance.now()) {
+        Ok(game) => game,
+        Err(err) => {
+            console::error_1(&err.into());
+            return;
+        }
+    };
+    game.stress(ROWS, COLUMNS);
+
+    let mut rng = Rng::new(SEED);
+    let mut heading_left = false;
+    let mut times: Vec<TimeStamp> = Vec::with_capacity(frames as usize);
+
+    let animation_closure = Rc::new(RefCell::new(None));
+    let animation_closure_initial = animation_closure.clone();
+    let closure_inner: Closure<dyn FnMut(TimeStamp)> = Closure::new(move |ts: TimeStamp| {
+        if rng.next_f64() < TURN_CHANCE {
+            heading_left = !heading_left;
+            game.send_input(GameInput::Left(heading_left));
+            game.send_input(GameInput::Right(!heading_left));
+        }
+        game.send_input(GameInput::Fire);
+
+        let start = performance.now();
+        game.tick(ts);
+        times.push(performance.now() - start);
+
+        if times.len() < frames as usize {
+            request_animation_frame(animation_closure.borrow().as_ref().unwrap());
+        } else {
+            report(&times);
+        }
+    });
+    *animation_closure_initial.borrow_mut() = Some(closure_inner);
+
+    request_animation_frame(animation_closure_initial.borrow().as_ref().unwrap());
+}
+
+fn report(times: &[TimeStamp]) {
+    let min = times
+        .iter()
+        .copied()
+        .fold(TimeStamp::INFINITY, TimeStamp::min);
+    let max = times.iter().copied().fold(0.0, TimeStamp::max);
+    let avg = times.iter().sum::<TimeStamp>() / times.len().max(1) as TimeStamp;
+    console::log_1(
+        &format!(
+            "Benchmark, {} frames of {}x{} fleet: min {:.3}ms, avg {:.3}ms, max {:.3}ms",
+            times.len(),
+            ROWS,
+            COLUMNS,
+            min,
+            avg,
+            max
+        )
+        .into(),
+    );
+}
+
+fn request_animation_frame(f: &Closure<dyn FnMut(TimeStamp)>) {
+    let window = web_sys::window().expect("no global `window` exists");
+    window
+        .request_animation_frame(f.as_ref().unchecked_ref())
+        .expect("should register `requestAnimationFrame` OK");
+}
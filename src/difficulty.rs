@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use crate::graphics::TimeStamp;
+
+/// Preset bundles of gameplay tuning, selected with the `data-difficulty` attribute on the game
+/// canvas (`easy`, `normal`, or `hard`), and adjusted with attributes of their own, see
+/// [`customize`](Self::customize)
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Custom(DifficultySettings),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DifficultySettings {
+    pub lives: u32,
+    /// Horizontal speed of the fleet before any speed-ups
+    pub fleet_rate: f64,
+    /// Time between enemy shots
+    pub enemy_fire_interval: TimeStamp,
+    pub bullet_rate: f64,
+    pub enemy_bullet_rate: f64,
+    /// Most player bullets allowed on screen at once
+    pub max_bullets: usize,
+}
+
+impl Difficulty {
+    pub fn settings(&self) -> DifficultySettings {
+        match self {
+            Difficulty::Easy => DifficultySettings {
+                lives: 5,
+                fleet_rate: 0.02,
+                enemy_fire_interval: 1500.0,
+                bullet_rate: 0.6,
+                enemy_bullet_rate: 0.15,
+                max_bullets: 5,
+            },
+            Difficulty::Normal => DifficultySettings {
+                lives: 3,
+                fleet_rate: 0.03,
+                enemy_fire_interval: 1000.0,
+                bullet_rate: 0.5,
+                enemy_bullet_rate: 0.2,
+                max_bullets: 3,
+            },
+            Difficulty::Hard => DifficultySettings {
+                lives: 1,
+                fleet_rate: 0.045,
+                enemy_fire_interval: 600.0,
+                bullet_rate: 0.45,
+                enemy_bullet_rate: 0.3,
+                max_bullets: 2,
+            },
+            Difficulty::Custom(settings) => *settings,
+        }
+    }
+
+    /// This difficulty with any of its settings that `attribute` gives a usable value for
+    /// replaced, making it a custom one: `data-lives`, `data-fleet-rate`, `data-fire-interval`,
+    /// `data-bullet-rate`, `data-enemy-bullet-rate`, and `data-max-bullets`
+    pub fn customize(self, attribute: impl Fn(&str) -> Option<String>) -> Self {
+        let mut settings = self.settings();
+        let mut customized = false;
+        let mut set = |value: Option<f64>, setting: &mut f64| {
+            if let Some(value) = value.filter(|value| *value > 0.0 && value.is_finite()) {
+                *setting = value;
+                customized = true;
+            }
+        };
+        let number = |name| attribute(name)?.trim().parse().ok();
+        set(number("data-fleet-rate"), &mut settings.fleet_rate);
+        set(
+            number("data-fire-interval"),
+            &mut settings.enemy_fire_interval,
+        );
+        set(number("data-bullet-rate"), &mut settings.bullet_rate);
+        set(
+            number("data-enemy-bullet-rate"),
+            &mut settings.enemy_bullet_rate,
+        );
+        // Counts have to be whole, and at least one
+        let count = |name| {
+            attribute(name)?
+                .trim()
+                .parse()
+                .ok()
+                .filter(|&count: &u32| count >= 1)
+        };
+        if let Some(lives) = count("data-lives") {
+            settings.lives = lives;
+            customized = true;
+        }
+        if let Some(max_bullets) = count("data-max-bullets") {
+            settings.max_bullets = max_bullets as usize;
+            customized = true;
+        }
+        if customized {
+            Difficulty::Custom(settings)
+        } else {
+            self
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            other => Err(format!("unknown difficulty \"{}\"", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes<'a>(pairs: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn attributes_customize_a_preset() {
+        let custom = Difficulty::Hard.customize(attributes(&[
+            ("data-lives", "4"),
+            ("data-fire-interval", "750"),
+            ("data-max-bullets", "0"),
+            ("data-fleet-rate", "fast"),
+        ]));
+        assert!(matches!(custom, Difficulty::Custom(_)));
+        let (settings, hard) = (custom.settings(), Difficulty::Hard.settings());
+        assert_eq!((settings.lives, settings.enemy_fire_interval), (4, 750.0));
+        // Unusable values leave the preset's setting alone
+        assert_eq!(settings.max_bullets, hard.max_bullets);
+        assert_eq!(settings.fleet_rate, hard.fleet_rate);
+        assert_eq!(settings.bullet_rate, hard.bullet_rate);
+    }
+
+    #[test]
+    fn a_preset_without_attributes_stays_as_it_is() {
+        let same = Difficulty::Easy.customize(attributes(&[("data-lives", "-1")]));
+        assert!(matches!(same, Difficulty::Easy));
+    }
+}
@@ -1,27 +1,435 @@
 mod icons;
 
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
 use wasm_bindgen::{Clamped, JsValue};
-use web_sys::{CanvasRenderingContext2d, ImageData};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::fire::{FireControl, FirePattern};
+use crate::geom::{Coordinates, Distance, OffsetStrategy, Position, Rect, Size, Vec2, XY};
+use crate::graphics::{self, Draw, DrawnRect, GameObject, TimeStamp, UpdateCtx};
+use crate::level::{CellDef, LevelDef, SpawnStyle};
+use crate::rng::Rng;
 
-use crate::geom::{Coordinates, Distance, OffsetStrategy, Position, Rect, Size, XY};
-use crate::graphics::{Draw, TimeStamp};
+/// Number of distinct enemy sprites, which level definitions refer to by index
+pub(crate) const ENEMY_SPRITES: usize = icons::ENEMIES.len();
+
+// High-contrast colors from the Okabe-Ito palette, which stays distinct under the common kinds of
+// color blindness; see `graphics::set_high_contrast`
+const HIGH_CONTRAST_SHIP: (u8, u8, u8) = (86, 180, 233);
+const HIGH_CONTRAST_BULLET: (u8, u8, u8) = (240, 228, 66);
+const HIGH_CONTRAST_BARRIER: (u8, u8, u8) = (0, 114, 178);
+const HIGH_CONTRAST_UFO: (u8, u8, u8) = (213, 94, 0);
+const HIGH_CONTRAST_ENEMIES: [(u8, u8, u8); ENEMY_SPRITES] =
+    [(230, 159, 0), (0, 158, 115), (204, 121, 167), (213, 94, 0)];
+const OUTLINE_COLOR: &str = "white";
 
 pub(crate) struct Entity {
+    /// Native size of the sprite in `data`; see [`Rect::size`] for the size on screen
     pub(crate) size: Size,
     pub(crate) position: Position,
-    pub(crate) data: Vec<u8>,
+    /// RGBA pixels, changed through [`data_mut`](Self::data_mut) so the caches below are dropped
+    ///
+    /// Entities made from the same icon share one buffer (see [`icons::shared`]), and one only
+    /// gets a copy of its own once its pixels are changed.
+    data: Rc<[u8]>,
+    /// The sprite as it was created, before any changes to `data`, for [`reset_pixels`]
+    ///
+    /// [`reset_pixels`]: Self::reset_pixels
+    original: Rc<[u8]>,
+    /// Clockwise rotation in radians around the entity's center
+    pub(crate) angle: f64,
+    /// Multiplier from the sprite's native size to its size on screen
+    pub(crate) scale: f64,
+    /// While set, the entity is drawn blended toward white, fading out until this time
+    pub(crate) flash_until: Option<TimeStamp>,
+    /// How opaque the entity is drawn, from `0.0` (invisible) to `1.0`
+    pub(crate) opacity: f64,
+    /// `data` as the `ImageData` that `put_image_data` takes, made on first use
+    image: Option<ImageData>,
+    sprite: Option<HtmlCanvasElement>,
+    silhouette: Option<HtmlCanvasElement>,
+    /// Counts changes to `data`, so a redraw can tell the sprite looks different in the same place
+    revision: u32,
+    /// Every pose of an animated sprite, in order, or none for a still one. The pose showing is
+    /// kept in `data`, and only put back here when the animation moves on from it.
+    frames: Vec<Rc<[u8]>>,
+    /// Which of `frames` is showing
+    frame: usize,
+    /// How long each of `frames` shows for
+    frame_ms: TimeStamp,
+    /// Time spent on the pose showing so far
+    frame_elapsed: TimeStamp,
 }
 
 impl Entity {
-    pub(crate) fn new(width: u32, height: u32, image: impl AsRef<[u8]>) -> Result<Self, JsValue> {
-        let data = image.as_ref().to_vec();
+    pub(crate) fn new(
+        width: u32,
+        height: u32,
+        image: impl Into<Rc<[u8]>>,
+    ) -> Result<Self, JsValue> {
+        let data = image.into();
 
         Ok(Self {
             size: Size::new(width.into(), height.into()),
             position: Default::default(),
+            original: Rc::clone(&data),
             data,
+            angle: 0.0,
+            scale: 1.0,
+            flash_until: None,
+            opacity: 1.0,
+            image: None,
+            sprite: None,
+            silhouette: None,
+            revision: 0,
+            frames: Vec::new(),
+            frame: 0,
+            frame_ms: 0.0,
+            frame_elapsed: 0.0,
         })
     }
+
+    /// An entity whose sprite cycles through `frames`, each shown for `frame_ms` of game time as
+    /// it's [advanced](Self::advance_frame). Changes to its pixels apply to every frame.
+    pub(crate) fn new_animated(
+        width: u32,
+        height: u32,
+        frames: Vec<Rc<[u8]>>,
+        frame_ms: TimeStamp,
+    ) -> Result<Self, JsValue> {
+        let first = frames
+            .first()
+            .cloned()
+            .ok_or_else(|| JsValue::from_str("An animated sprite needs at least one frame"))?;
+        let mut entity = Self::new(width, height, first)?;
+        entity.frames = frames;
+        entity.frame_ms = frame_ms;
+        Ok(entity)
+    }
+
+    /// Move the animation on by `elapsed` game time, wrapping around from the last frame to the
+    /// first. Still sprites are left alone.
+    pub(crate) fn advance_frame(&mut self, elapsed: TimeStamp) {
+        if self.frames.len() < 2 || !(self.frame_ms > 0.0 && self.frame_ms.is_finite()) {
+            return;
+        }
+        self.frame_elapsed += elapsed;
+        let steps = (self.frame_elapsed / self.frame_ms).floor();
+        if steps < 1.0 {
+            return;
+        }
+        self.frame_elapsed -= steps * self.frame_ms;
+        let next = (self.frame + (steps as usize % self.frames.len())) % self.frames.len();
+        self.invalidate();
+        let showing = std::mem::replace(&mut self.data, Rc::clone(&self.frames[next]));
+        self.frames[self.frame] = showing;
+        self.frame = next;
+    }
+
+    /// An entity whose sprite is the bundled PNG `icon`, sized to match it, and sharing its
+    /// pixels with every other entity made from the same icon
+    pub(crate) fn from_png(icon: &'static [u8]) -> Result<Self, JsValue> {
+        let icon = icons::shared(icon);
+        Self::new(icon.width, icon.height, icon.pixels)
+    }
+
+    /// How long a [`flash`](Self::flash) takes to fade out, in milliseconds
+    const FLASH_MS: TimeStamp = 100.0;
+
+    /// Briefly flash the entity white from game time `ts`, e.g. on a hit it survives. The flash
+    /// fades against [`graphics::now`] as it's drawn.
+    pub(crate) fn flash(&mut self, ts: TimeStamp) {
+        self.flash_until = Some(ts + Self::FLASH_MS);
+    }
+
+    /// Strength of the white flash right now, from 0 (none) to 1 (fully white)
+    fn flash_strength(&mut self) -> f64 {
+        let Some(until) = self.flash_until else {
+            return 0.0;
+        };
+        let remaining = until - graphics::now();
+        if remaining <= 0.0 {
+            self.flash_until = None;
+            return 0.0;
+        }
+        (remaining / Self::FLASH_MS).min(1.0)
+    }
+
+    /// Make the pixels within `radius` of (`x`, `y`), in the sprite's own pixels, fully
+    /// transparent. A pixel is cleared if its center falls inside the circle.
+    pub(crate) fn clear_circle(&mut self, x: Distance, y: Distance, radius: Distance) {
+        let width = self.size.x() as usize;
+        let clip = |center: Distance, limit: usize| {
+            ((center - radius).floor().max(0.0) as usize)
+                ..((center + radius).ceil().max(0.0) as usize).min(limit)
+        };
+        let columns = clip(x, width);
+        let rows = clip(y, self.size.y() as usize);
+        self.each_frame_mut(|data| {
+            for row in rows.clone() {
+                for column in columns.clone() {
+                    let (dx, dy) = (column as Distance + 0.5 - x, row as Distance + 0.5 - y);
+                    if dx.hypot(dy) <= radius {
+                        data[((row * width) + column) * 4 + 3] = 0;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Mirror the sprite left to right, e.g. to face it the other way. Flipping twice puts it
+    /// back as it was.
+    pub(crate) fn flip_horizontal(&mut self) {
+        let width = self.size.x() as usize;
+        self.each_frame_mut(|data| icons::mirror(data, width));
+    }
+
+    /// Multiply the color of every pixel in the sprite by `(r, g, b)`, leaving their alpha alone,
+    /// so white parts take on the tint and black parts stay black
+    pub(crate) fn tint(&mut self, r: u8, g: u8, b: u8) {
+        let (width, height) = (self.size.x() as u32, self.size.y() as u32);
+        self.tint_region(0, 0, width, height, (r, g, b));
+    }
+
+    /// Multiply the color of the pixels in a rectangular region of the sprite by `(r, g, b)`,
+    /// leaving their alpha alone. The region is clipped to the sprite's bounds.
+    pub(crate) fn tint_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        (r, g, b): (u8, u8, u8),
+    ) {
+        self.update_region(x, y, width, height, |pixel| {
+            for (channel, tint) in pixel[..3].iter_mut().zip([r, g, b]) {
+                *channel = ((u16::from(*channel) * u16::from(tint)) / 255) as u8;
+            }
+        });
+    }
+
+    /// In high-contrast mode, repaint the sprite in flat `color`, with its darkest details kept a
+    /// shade darker so the shape still reads. Transparent pixels are left transparent.
+    pub(crate) fn with_palette(mut self, color: (u8, u8, u8)) -> Self {
+        if !graphics::high_contrast() {
+            return self;
+        }
+        let (width, height) = (self.size.x() as u32, self.size.y() as u32);
+        self.update_region(0, 0, width, height, |pixel| {
+            let luma =
+                (299 * u32::from(pixel[0]) + 587 * u32::from(pixel[1]) + 114 * u32::from(pixel[2]))
+                    / 1000;
+            let level = if luma < 64 { 96 } else { 255 };
+            for (channel, color) in pixel[..3].iter_mut().zip([color.0, color.1, color.2]) {
+                *channel = ((u32::from(color) * level) / 255) as u8;
+            }
+        });
+        // The recolored sprite is what this entity starts out as, so it's what a reset goes back to
+        self.original = Rc::clone(&self.data);
+        self
+    }
+
+    /// Undo every change made to the sprite's pixels since it was created, like tinting or
+    /// erosion
+    pub(crate) fn reset_pixels(&mut self) {
+        if *self.data != *self.original {
+            self.invalidate();
+            self.data = Rc::clone(&self.original);
+        }
+    }
+
+    /// The sprite's RGBA pixels, for changing. Anything cached from the old pixels is dropped, to
+    /// be made again from the new ones when next drawn.
+    pub(crate) fn data_mut(&mut self) -> &mut [u8] {
+        self.invalidate();
+        Rc::make_mut(&mut self.data)
+    }
+
+    /// Drop everything cached from the sprite's pixels, as they're about to change
+    fn invalidate(&mut self) {
+        self.image = None;
+        self.sprite = None;
+        self.silhouette = None;
+        self.revision += 1;
+    }
+
+    /// Apply `f` to each RGBA pixel in a region of the sprite, clipped to its bounds
+    fn update_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        mut f: impl FnMut(&mut [u8]),
+    ) {
+        let sprite_width = self.size.x() as usize;
+        let sprite_height = self.size.y() as usize;
+        let clip = |start: u32, length: u32, limit: usize| {
+            (start as usize).min(limit)..(start as usize).saturating_add(length as usize).min(limit)
+        };
+        let columns = clip(x, width, sprite_width);
+        let rows = clip(y, height, sprite_height);
+
+        self.each_frame_mut(|data| {
+            for row in rows.clone() {
+                let start = ((row * sprite_width) + columns.start) * 4;
+                let end = ((row * sprite_width) + columns.end) * 4;
+                data[start..end].chunks_exact_mut(4).for_each(&mut f);
+            }
+        });
+    }
+
+    /// Apply `f` to the pixels showing, and to every other frame of an animated sprite
+    fn each_frame_mut(&mut self, mut f: impl FnMut(&mut [u8])) {
+        f(self.data_mut());
+        let showing = self.frame;
+        for (i, frame) in self.frames.iter_mut().enumerate() {
+            if i != showing {
+                f(Rc::make_mut(frame));
+            }
+        }
+    }
+
+    /// Whether the sprite has a visible (not fully transparent) pixel at (`x`, `y`), in the same
+    /// coordinates as its position. Rotation is ignored, the same as for bounding boxes.
+    fn opaque_at(&self, x: Distance, y: Distance) -> bool {
+        if !self.contains_point(x, y) {
+            return false;
+        }
+        let column = ((x - self.position.x()) / self.scale).floor();
+        let row = ((y - self.position.y()) / self.scale).floor();
+        // Rounding can still put a point just inside the far edges one pixel past the sprite
+        if column >= self.size.x() || row >= self.size.y() {
+            return false;
+        }
+        let i = ((row as usize * self.size.x() as usize) + column as usize) * 4;
+        self.data[i + 3] != 0
+    }
+
+    /// Whether this entity and `other` overlap where both of their sprites are visible, rather
+    /// than just their bounding boxes overlapping, so transparent corners don't count as hits
+    ///
+    /// Only the overlap of the two boxes is checked, a pixel at a time, but that can still be
+    /// hundreds of checks for large sprites where [`Rect::intersects`] is a handful of
+    /// comparisons, so this is only worth it after the boxes are known to overlap (which it
+    /// checks first anyway).
+    pub(crate) fn collides_pixel(&self, other: &Entity) -> bool {
+        if !self.intersects(other) {
+            return false;
+        }
+        let (origin, extent) = (self.position(), self.extent());
+        let (other_origin, other_extent) = (other.position(), other.extent());
+        let left = origin.x().max(other_origin.x());
+        let top = origin.y().max(other_origin.y());
+        let right = extent.x().min(other_extent.x());
+        let bottom = extent.y().min(other_extent.y());
+        // Sample the middle of each pixel of the overlap; samples falling just outside it are
+        // outside one sprite or the other, so never count
+        let mut y = top.floor() + 0.5;
+        while y < bottom {
+            let mut x = left.floor() + 0.5;
+            while x < right {
+                if self.opaque_at(x, y) && other.opaque_at(x, y) {
+                    return true;
+                }
+                x += 1.0;
+            }
+            y += 1.0;
+        }
+        false
+    }
+
+    /// Where and how this entity is drawn with a `flash` of the given strength, for
+    /// [`graphics::measure`]
+    fn drawn_rect(&self, flash: f64) -> DrawnRect {
+        let size = self.size();
+        let mut look = DefaultHasher::new();
+        for value in [self.angle, self.opacity, flash] {
+            value.to_bits().hash(&mut look);
+        }
+        self.revision.hash(&mut look);
+        // A rotated sprite's corners swing out past its box, but never beyond its diagonal
+        let (width, height) = if self.angle == 0.0 {
+            (size.x(), size.y())
+        } else {
+            let diagonal = size.length();
+            (diagonal, diagonal)
+        };
+        DrawnRect {
+            x: self.position.x() + ((size.x() - width) / 2.0),
+            y: self.position.y() + ((size.y() - height) / 2.0),
+            width,
+            height,
+            look: look.finish(),
+        }
+    }
+
+    /// Stroke a 1px outline along the inside edge of the box at (`x`, `y`)
+    fn draw_outline(
+        context: &CanvasRenderingContext2d,
+        x: Distance,
+        y: Distance,
+        width: Distance,
+        height: Distance,
+    ) {
+        context.save();
+        context.set_stroke_style_str(OUTLINE_COLOR);
+        context.set_line_width(1.0);
+        // Strokes are centered on the path, so inset by half a pixel to keep all of it inside
+        context.stroke_rect(x + 0.5, y + 0.5, width - 1.0, height - 1.0);
+        context.restore();
+    }
+
+    /// This entity's pixels as `ImageData`, made on first use rather than copied out of wasm
+    /// memory again every frame
+    fn image_data(&mut self) -> Result<&ImageData, JsValue> {
+        if self.image.is_none() {
+            self.image = Some(ImageData::new_with_u8_clamped_array_and_sh(
+                Clamped(&self.data[..]),
+                self.size.x() as u32,
+                self.size.y() as u32,
+            )?);
+        }
+        Ok(self.image.as_ref().unwrap())
+    }
+
+    /// This entity's pixels on an offscreen canvas from the [`graphics::sprite`] cache, looked
+    /// up on first use
+    ///
+    /// `put_image_data` ignores the context's transform and can't resize, so any draw that rotates
+    /// or scales has to go through `draw_image` with a canvas (or other image) as its source.
+    fn sprite(&mut self) -> Result<&HtmlCanvasElement, JsValue> {
+        if self.sprite.is_none() {
+            self.sprite = Some(graphics::sprite(
+                self.size.x() as u32,
+                self.size.y() as u32,
+                &self.data,
+            )?);
+        }
+        Ok(self.sprite.as_ref().unwrap())
+    }
+
+    /// Like [`sprite`](Self::sprite), but with every visible pixel turned white, for flashing
+    fn silhouette(&mut self) -> Result<&HtmlCanvasElement, JsValue> {
+        if self.silhouette.is_none() {
+            let mut data = self.data.to_vec();
+            for pixel in data.chunks_exact_mut(4) {
+                if pixel[3] != 0 {
+                    pixel[..3].fill(u8::MAX);
+                }
+            }
+            self.silhouette = Some(graphics::sprite(
+                self.size.x() as u32,
+                self.size.y() as u32,
+                &data,
+            )?);
+        }
+        Ok(self.silhouette.as_ref().unwrap())
+    }
 }
 
 impl Rect for Entity {
@@ -34,7 +442,7 @@ impl Rect for Entity {
     }
 
     fn size(&self) -> Size {
-        self.size
+        Size::new(self.size.x() * self.scale, self.size.y() * self.scale)
     }
 }
 
@@ -42,233 +450,2685 @@ impl Draw for Entity {
     fn draw(&mut self, context: &CanvasRenderingContext2d) {
         let x = self.position.x();
         let y = self.position.y();
-        let width = self.size.x();
-        let height = self.size.y();
 
-        let image = ImageData::new_with_u8_clamped_array_and_sh(
-            Clamped(&self.data),
-            width as u32,
-            height as u32,
-        )
-        .expect("ImageData");
+        let flash = self.flash_strength();
+        if graphics::measuring() {
+            graphics::record(self.drawn_rect(flash));
+            return;
+        }
 
-        context
-            .put_image_data(&image, x, y)
-            .expect("put_image_data");
-    }
-}
+        let plain = self.angle == 0.0 && self.scale == 1.0 && flash == 0.0 && self.opacity >= 1.0;
+        if plain && !graphics::subpixel() && !graphics::blend() && graphics::zoom() == 1 {
+            let image = self.image_data().expect("ImageData");
+            context.put_image_data(image, x, y).expect("put_image_data");
+            if graphics::outline() {
+                let size = self.size();
+                // `put_image_data` lands on whole pixels, so the outline does too
+                Self::draw_outline(context, x.trunc(), y.trunc(), size.x(), size.y());
+            }
+        } else {
+            let size = self.size();
+            let (width, height) = (size.x(), size.y());
+            // Land on whole pixels like `put_image_data` does, unless sub-pixel rendering is on,
+            // so that sprites aren't smeared across pixel boundaries
+            let (x, y) = if graphics::subpixel() {
+                (x, y)
+            } else {
+                (x.trunc(), y.trunc())
+            };
 
-#[derive(Default)]
-pub(crate) enum Direction {
-    Left,
-    #[default]
-    Stopped,
-    Right,
+            context.save();
+            context
+                .translate(x + (width / 2.0), y + (height / 2.0))
+                .expect("translate");
+            context.rotate(self.angle).expect("rotate");
+            context.set_global_alpha(self.opacity);
+            context
+                .draw_image_with_html_canvas_element_and_dw_and_dh(
+                    self.sprite().expect("sprite canvas"),
+                    -width / 2.0,
+                    -height / 2.0,
+                    width,
+                    height,
+                )
+                .expect("draw_image");
+            if flash > 0.0 {
+                // The flash is composited from a white copy of the sprite over the original,
+                // which only works on this `draw_image` path
+                context.set_global_alpha(flash * self.opacity);
+                context
+                    .draw_image_with_html_canvas_element_and_dw_and_dh(
+                        self.silhouette().expect("silhouette canvas"),
+                        -width / 2.0,
+                        -height / 2.0,
+                        width,
+                        height,
+                    )
+                    .expect("draw_image");
+            }
+            if graphics::outline() {
+                Self::draw_outline(context, -width / 2.0, -height / 2.0, width, height);
+            }
+            context.restore();
+        }
+    }
 }
 
 pub(crate) struct Ship {
     pub(crate) inner: Entity,
-    pub(crate) direction: Direction,
+    /// An x position to steer the middle of the ship to, in place of following the left and
+    /// right controls
+    pub(crate) target_x: Option<Distance>,
     pub(crate) rate: f64,
-    bullets: Vec<Bullet>,
+    pub(crate) bullet_rate: f64,
+    /// Most bullets the ship can have on screen at once; shots beyond this are ignored
+    pub(crate) max_bullets: usize,
+    /// Minimum time between shots
+    pub(crate) cooldown_ms: TimeStamp,
+    pub(crate) last_shot_ts: TimeStamp,
+    /// How long a shot attempted during the cooldown stays queued, to be fired as soon as the
+    /// cooldown clears
+    pub(crate) fire_buffer_ms: TimeStamp,
+    /// When a shot was attempted during the cooldown and is still waiting to be fired
+    pending_shot: Option<TimeStamp>,
+    /// Afterimages for the ship's bullets, if any
+    pub(crate) bullet_trail: Option<Trail>,
+    /// When the spread shot power-up wears off, while it's in effect
+    spread_until: Option<TimeStamp>,
+    /// When the rapid fire power-up wears off, while it's in effect
+    rapid_until: Option<TimeStamp>,
+    bullets: BulletPool,
+    /// Whether bullets only hit the ship where its sprite is visible, see
+    /// [`Entity::collides_pixel`]
+    pub(crate) pixel_collisions: bool,
+    pub(crate) lives: u32,
+    /// Which way the sprite points, `-1.0` for left (as the icon is drawn) or `1.0` for right
+    facing: Distance,
+    left_bound: Distance,
+    right_bound: Distance,
 }
 
 impl Ship {
+    /// Radians of tilt per unit of horizontal velocity (pixels per millisecond)
+    const TILT: f64 = 0.2;
+    pub(crate) const FIRE_BUFFER_MS: TimeStamp = 120.0;
+    pub(crate) const COOLDOWN_MS: TimeStamp = 250.0;
+    pub(crate) const DEFAULT_LIVES: u32 = 3;
+    /// Sideways speed of a spread shot's outer bullets, as a fraction of their speed up the screen
+    const SPREAD: f64 = 0.25;
+    /// How long the spread shot and rapid fire power-ups last
+    const BOOST_MS: TimeStamp = 8000.0;
+
+    /// The ship rests at `y_position`, and can be steered up to `y_range` above it; a `y_range` of
+    /// zero keeps it moving along a single row
     pub(crate) fn new(
         rate: f64,
+        bullet_rate: f64,
         y_position: Distance,
+        y_range: Distance,
         left_bound: Distance,
         right_bound: Distance,
     ) -> Self {
-        let mut inner = Entity::new(icons::SHIP_WIDTH, icons::SHIP_HEIGHT, icons::SHIP).unwrap();
+        let mut inner = Entity::from_png(icons::SHIP)
+            .unwrap()
+            .with_palette(HIGH_CONTRAST_SHIP);
+        let size = inner.size();
         let position = inner.position_mut();
-        position.set_offset_x(OffsetStrategy::limit(
-            left_bound,
-            right_bound - Distance::from(icons::SHIP_WIDTH),
-        ));
-        let center = left_bound
-            + ((right_bound - left_bound) / 2.0)
-            + (Distance::from(icons::SHIP_WIDTH) / 2.0);
-        position.set_x(center);
-        position.set_offset_y(OffsetStrategy::limit(y_position, y_position));
-        position.set_y(y_position - Distance::from(icons::SHIP_HEIGHT));
+        position.set_offset_x(OffsetStrategy::limit(left_bound, right_bound - size.x()));
+        position.set_x(Self::center_x(left_bound, right_bound, size.x()));
+        position.set_offset_y(OffsetStrategy::limit(y_position - y_range, y_position));
+        position.set_y(y_position - size.y());
 
         Self {
             inner,
-            direction: Default::default(),
+            target_x: None,
             rate,
-            bullets: Vec::new(),
+            bullet_rate,
+            max_bullets: usize::MAX,
+            cooldown_ms: Self::COOLDOWN_MS,
+            last_shot_ts: TimeStamp::NEG_INFINITY,
+            fire_buffer_ms: Self::FIRE_BUFFER_MS,
+            pending_shot: None,
+            bullet_trail: None,
+            spread_until: None,
+            rapid_until: None,
+            bullets: BulletPool::new(),
+            pixel_collisions: false,
+            lives: Self::DEFAULT_LIVES,
+            facing: -1.0,
+            left_bound,
+            right_bound,
         }
     }
 
-    pub(crate) fn animate(&mut self, context: &CanvasRenderingContext2d, offset_ts: TimeStamp) {
-        let offset = offset_ts * self.rate;
-        match self.direction {
-            Direction::Left => self.inner.position_mut().offset(-offset, 0.0),
-            Direction::Right => self.inner.position_mut().offset(offset, 0.0),
-            Direction::Stopped => {}
+    /// The x position that puts a ship `width` across in the middle of `left_bound` to
+    /// `right_bound`, with equal space on either side of the sprite
+    fn center_x(left_bound: Distance, right_bound: Distance, width: Distance) -> Distance {
+        left_bound + ((right_bound - left_bound) / 2.0) - (width / 2.0)
+    }
+
+    /// Lose a life at game time `ts`, flashing the ship and sending it back to the middle of its
+    /// range, and return whether it has any lives left
+    pub(crate) fn hit(&mut self, ts: TimeStamp) -> bool {
+        self.lives = self.lives.saturating_sub(1);
+        self.inner.flash(ts);
+        let x = Self::center_x(self.left_bound, self.right_bound, self.inner.size().x());
+        self.inner.position_mut().set_x(x);
+        self.lives > 0
+    }
+
+    /// Take a hit at game time `ts` from each of `bullets` that touches the ship, removing those
+    /// bullets, and return whether the ship has any lives left
+    pub(crate) fn take_hits(&mut self, bullets: &mut BulletPool, ts: TimeStamp) -> bool {
+        bullets.retain(|bullet| {
+            // Each hit moves the ship, so later bullets are tested against where it ends up
+            let hit = if self.pixel_collisions {
+                self.inner.collides_pixel(&bullet.inner)
+            } else {
+                self.inner.intersects(&bullet.inner)
+            };
+            if self.lives > 0 && hit {
+                self.hit(ts);
+                false
+            } else {
+                true
+            }
+        });
+        self.lives > 0
+    }
+
+    /// Put the middle of the ship at `x`, as near as its range allows, like a mouse cursor
+    /// steering it directly rather than by the controls
+    pub(crate) fn move_to(&mut self, x: Distance) {
+        let width = self.inner.size().x();
+        let position = self.inner.position_mut();
+        position.set_x(x - (width / 2.0));
+        position.offset(0.0, 0.0);
+    }
+
+    /// Let the ship run off one side of its range and come back in on the other, rather than
+    /// stopping at the edges
+    pub(crate) fn set_wrap(&mut self, wrap: bool) {
+        let strategy = if wrap {
+            OffsetStrategy::wrap(self.left_bound, self.right_bound)
+        } else {
+            OffsetStrategy::limit(self.left_bound, self.right_bound - self.inner.size().x())
+        };
+        let position = self.inner.position_mut();
+        position.set_offset_x(strategy);
+        // Apply the new strategy's range to where the ship is now
+        position.offset(0.0, 0.0);
+    }
+
+    /// Move the ship's range to run from `left_bound` to `right_bound`, and its row down by
+    /// `shift_y`, pulling the ship back inside if it's now out of range
+    pub(crate) fn set_bounds(
+        &mut self,
+        left_bound: Distance,
+        right_bound: Distance,
+        shift_y: Distance,
+    ) {
+        self.left_bound = left_bound;
+        self.right_bound = right_bound;
+        let width = self.inner.size().x();
+        let position = self.inner.position_mut();
+        let strategy = position.offset_x();
+        // A wrapping ship uses the whole range, otherwise it has to fit inside
+        let max = match strategy {
+            OffsetStrategy::Wrap { .. } => right_bound,
+            _ => right_bound - width,
+        };
+        position.set_offset_x(strategy.with_bounds(left_bound, max));
+        let strategy = position.offset_y();
+        if let Some((min, max)) = strategy.bounds() {
+            position.set_offset_y(strategy.with_bounds(min + shift_y, max + shift_y));
         }
+        let y = position.y();
+        position.set_y(y + shift_y);
+        position.offset(0.0, 0.0);
+    }
+
+    /// Have the ship glide, speeding up and slowing down over a few frames rather than starting
+    /// and stopping at once, with `acceleration` the share of the change in speed taken each frame
+    pub(crate) fn set_glide(&mut self, acceleration: f64) {
+        let strategy = OffsetStrategy::ease_in_out(
+            self.left_bound,
+            self.right_bound - self.inner.size().x(),
+            acceleration,
+        );
+        let position = self.inner.position_mut();
+        position.set_offset_x(strategy);
+        position.offset(0.0, 0.0);
+    }
+
+    /// Draw the ship, and while it's wrapping around and straddling the right edge of its range,
+    /// a second time coming back in at the left so it doesn't pop from one side to the other.
+    /// The ship's own position stays the logical one, past the right edge.
+    fn draw_wrapped(&mut self, context: &CanvasRenderingContext2d) {
         self.inner.draw(context);
-        // Way better to use nightly's drain_filter here. Alas.
-        let mut i = 0;
-        while i < self.bullets.len() {
-            if self.bullets[i].inner.position().y() < -(f64::from(icons::BULLET_HEIGHT)) {
-                // swap_remove more performant here, becuase
-                // bullet iteration order doesn't matter
-                self.bullets.swap_remove(i);
-            } else {
-                self.bullets[i].animate(context, offset_ts);
-                i += 1;
+        let position = self.inner.position();
+        let wrapping = matches!(position.offset_x(), OffsetStrategy::Wrap { .. });
+        if wrapping && self.inner.extent().x() > self.right_bound {
+            let x = position.x();
+            let range = self.right_bound - self.left_bound;
+            self.inner.position_mut().set_x(x - range);
+            self.inner.draw(context);
+            self.inner.position_mut().set_x(x);
+        }
+    }
+
+    /// Move the ship and its bullets along, steered by the controls held in `ctx.input`, and fire
+    /// any shot that was waiting on the cooldown
+    pub(crate) fn update(&mut self, ctx: &UpdateCtx) {
+        let offset_ts = ctx.offset_ts;
+        let offset = offset_ts * self.rate;
+        let start_x = self.inner.position().x();
+        // Head for the target at full speed, without overshooting it
+        let offset_x = match self.target_x {
+            Some(x) => (x - start_x - (self.inner.size().x() / 2.0)).clamp(-offset, offset),
+            None => ctx.input.x() * offset,
+        };
+        self.inner
+            .position_mut()
+            .offset(offset_x, ctx.input.y() * offset);
+        // Lean into the direction of travel, measured from actual movement so the ship sits
+        // upright when stopped or pinned against a bound. A wrapping ship has no bounds to be
+        // pinned against, and measuring would see it jump back across the screen as it wraps.
+        if offset_ts > 0.0 {
+            let moved = match self.inner.position().offset_x() {
+                OffsetStrategy::Wrap { .. } => offset_x,
+                _ => self.inner.position().x() - start_x,
+            };
+            let velocity = moved / offset_ts;
+            self.inner.angle = velocity * Self::TILT;
+            // Turn to face the way it's moving, and keep facing that way once it stops
+            if velocity != 0.0 && velocity.signum() != self.facing {
+                self.inner.flip_horizontal();
+                self.facing = velocity.signum();
+            }
+        }
+        self.fire_pending(ctx.ts);
+        self.bullets.update(ctx);
+    }
+
+    /// Fire the queued shot if the cooldown has cleared by `ts`, or drop it once it's been
+    /// waiting longer than the buffer window
+    fn fire_pending(&mut self, ts: TimeStamp) {
+        let Some(requested) = self.pending_shot else {
+            return;
+        };
+        if ts - requested > self.fire_buffer_ms {
+            self.pending_shot = None;
+        } else if self.cooldown_progress(ts) >= 1.0 {
+            self.pending_shot = None;
+            self.fire(ts);
+        }
+    }
+
+    /// Whether the ship is pinned against the left edge of its range
+    pub(crate) fn at_left_bound(&self) -> bool {
+        self.x_limits()
+            .is_some_and(|(min, _)| self.inner.position().x() <= min)
+    }
+
+    /// Whether the ship is pinned against the right edge of its range
+    pub(crate) fn at_right_bound(&self) -> bool {
+        self.x_limits()
+            .is_some_and(|(_, max)| self.inner.position().x() >= max)
+    }
+
+    /// The range the ship's x position is held within, or `None` if it wraps around instead and
+    /// can never be pinned
+    fn x_limits(&self) -> Option<(Distance, Distance)> {
+        match self.inner.position().offset_x() {
+            OffsetStrategy::Wrap { .. } => None,
+            strategy => strategy.bounds(),
+        }
+    }
+
+    /// Fire a bullet at time `ts`, unless the ship is still cooling down from its last shot or
+    /// already has as many bullets in flight as it's allowed. Returns whether a shot was fired.
+    ///
+    /// A shot attempted during the cooldown is queued instead, and fired from
+    /// [`update`](Self::update) as soon as the cooldown clears, provided that happens within
+    /// `fire_buffer_ms`.
+    pub(crate) fn shoot(&mut self, ts: TimeStamp) -> bool {
+        if self.cooldown_progress(ts) < 1.0 {
+            if self.fire_buffer_ms > 0.0 {
+                self.pending_shot = Some(ts);
             }
+            return false;
         }
+        self.fire(ts)
     }
 
-    pub(crate) fn shoot(&mut self) {
-        let position = Position::new(
+    /// Where the top-left corner of a newly fired bullet is placed
+    fn gun_position(&self) -> Position {
+        Position::new(
             self.inner.position().x() + 11.0,
             self.inner.position().y() + 10.0,
-        );
-        let bullet = Bullet::new(position);
-        self.bullets.push(bullet);
+        )
+    }
+
+    /// Faintly trace the path a shot fired now would take, from the gun up to the top of the play
+    /// area, outlining the member of `fleet` it would hit first
+    pub(crate) fn draw_firing_line(&self, context: &CanvasRenderingContext2d, fleet: &Fleet) {
+        let gun = self.gun_position();
+        let bullet_width = Bullet::width();
+        // The bullet sprite is placed at the gun, so its middle is where the shot visibly travels
+        let x = gun.x() + (bullet_width / 2.0);
+        let target = fleet.first_in_line(gun.x(), gun.x() + bullet_width);
+        let top = target.map_or(0.0, |member| member.inner.extent().y());
+
+        context.save();
+        context.set_stroke_style_str("rgba(255, 255, 255, 0.25)");
+        context.set_line_width(1.0);
+        context.begin_path();
+        context.move_to(x, gun.y());
+        context.line_to(x, top);
+        context.stroke();
+        if let Some(member) = target {
+            let position = member.inner.position();
+            let size = member.inner.size();
+            context.set_stroke_style_str("rgba(255, 64, 64, 0.6)");
+            context.stroke_rect(
+                position.x() - 1.0,
+                position.y() - 1.0,
+                size.x() + 2.0,
+                size.y() + 2.0,
+            );
+        }
+        context.restore();
+    }
+
+    /// Fire three bullets at a time, one straight up and two angled out to the sides, until `ts`
+    pub(crate) fn spread_shot_until(&mut self, ts: TimeStamp) {
+        self.spread_until = Some(ts);
+    }
+
+    /// Whether the spread shot power-up is in effect at time `ts`
+    pub(crate) fn spread_shot(&self, ts: TimeStamp) -> bool {
+        self.spread_until.is_some_and(|until| ts < until)
+    }
+
+    /// A spread shot counts as one against `max_bullets`, so it goes out whole or not at all
+    fn fire(&mut self, ts: TimeStamp) -> bool {
+        if self.bullets.len() >= self.max_bullets {
+            return false;
+        }
+        let gun = self.gun_position();
+        let sideways: &[f64] = if self.spread_shot(ts) {
+            &[0.0, -Self::SPREAD, Self::SPREAD]
+        } else {
+            &[0.0]
+        };
+        for &sideways in sideways {
+            let velocity = Vec2::new(sideways * self.bullet_rate, -self.bullet_rate);
+            self.bullets.fire(gun, velocity).trail = self.bullet_trail;
+        }
+        self.last_shot_ts = ts;
+        true
+    }
+
+    /// The ship's bullets in flight, to be checked for hits
+    pub(crate) fn bullets_mut(&mut self) -> &mut BulletPool {
+        &mut self.bullets
+    }
+
+    /// Give the ship what a caught power-up holds, as of time `ts`
+    pub(crate) fn boost(&mut self, boost: Boost, ts: TimeStamp) {
+        match boost {
+            Boost::ExtraLife => self.lives += 1,
+            Boost::SpreadShot => self.spread_shot_until(ts + Self::BOOST_MS),
+            Boost::RapidFire => self.rapid_until = Some(ts + Self::BOOST_MS),
+        }
+    }
+
+    /// Catch each of `power_ups` that touches the ship at time `ts`, removing them, and return
+    /// what they gave it
+    pub(crate) fn catch(&mut self, power_ups: &mut Vec<PowerUp>, ts: TimeStamp) -> Vec<Boost> {
+        let mut caught = Vec::new();
+        power_ups.retain(|power_up| {
+            if self.inner.intersects(&power_up.inner) {
+                caught.push(power_up.boost);
+                false
+            } else {
+                true
+            }
+        });
+        for &boost in &caught {
+            self.boost(boost, ts);
+        }
+        caught
+    }
+
+    /// Fraction of the firing cooldown that has passed at time `ts`, reaching 1 when the ship is
+    /// ready to fire again. Rapid fire halves the cooldown.
+    pub(crate) fn cooldown_progress(&self, ts: TimeStamp) -> f64 {
+        let rapid = self.rapid_until.is_some_and(|until| ts < until);
+        let cooldown_ms = if rapid {
+            self.cooldown_ms / 2.0
+        } else {
+            self.cooldown_ms
+        };
+        if cooldown_ms <= 0.0 {
+            return 1.0;
+        }
+        ((ts - self.last_shot_ts) / cooldown_ms).clamp(0.0, 1.0)
+    }
+
+    /// Draw a thin bar just above the ship that fills up as the firing cooldown at time `ts`
+    /// runs out
+    pub(crate) fn draw_cooldown_bar(&self, context: &CanvasRenderingContext2d, ts: TimeStamp) {
+        const HEIGHT: Distance = 3.0;
+        let position = self.inner.position();
+        let width = self.inner.size().x();
+        let y = position.y() - (2.0 * HEIGHT);
+        let progress = self.cooldown_progress(ts);
+        if graphics::measuring() {
+            graphics::record(DrawnRect {
+                x: position.x(),
+                y,
+                width,
+                height: HEIGHT,
+                look: progress.to_bits(),
+            });
+            return;
+        }
+
+        context.set_fill_style_str("rgba(128, 128, 128, 0.4)");
+        context.fill_rect(position.x(), y, width, HEIGHT);
+        context.set_fill_style_str("rgb(64, 192, 255)");
+        context.fill_rect(position.x(), y, width * progress, HEIGHT);
+    }
+}
+
+impl Draw for Ship {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        self.draw_wrapped(context);
+        self.bullets.draw(context);
+    }
+}
+
+/// A member of a [`Fleet`]
+pub(crate) struct Enemy {
+    pub(crate) inner: Entity,
+    /// Which of the standard enemy sprites this is, which also sets its type and point value
+    pub(crate) kind: usize,
+    /// Hits it can still take, one for most members
+    pub(crate) health: u32,
+}
+
+impl Enemy {
+    /// How long each pose shows for while the fleet is moving
+    const FRAME_MS: TimeStamp = 500.0;
+
+    pub(crate) fn new(kind: usize) -> Self {
+        // The classic two-step shuffle, between the icon and its mirror image
+        let icon = icons::shared(icons::ENEMIES[kind]);
+        let poses = vec![icon.pixels, icons::mirrored(icons::ENEMIES[kind]).pixels];
+        let inner = Entity::new_animated(icon.width, icon.height, poses, Self::FRAME_MS)
+            .expect("Block")
+            .with_palette(HIGH_CONTRAST_ENEMIES[kind]);
+        Self {
+            inner,
+            kind,
+            health: 1,
+        }
+    }
+
+    /// Size of a member's sprite, which every kind shares so that they line up in a grid
+    pub(crate) fn size() -> Size {
+        let (width, height) = icons::size(icons::ENEMIES[0]);
+        Size::new(width.into(), height.into())
     }
 }
 
 pub(crate) struct Fleet {
     pub(crate) size: Size,
     pub(crate) position: Position,
-    pub(crate) rate: f64,
+    /// Sideways speed, in distance per millisecond
+    pub(crate) horizontal_rate: f64,
     pub(crate) spacing: Distance,
-    pub(crate) members: Vec<Vec<Entity>>,
+    /// While set, the fleet holds still (keeping its place in its movement cycle) but is still
+    /// drawn, independent of whether the rest of the game is paused
+    pub(crate) frozen: bool,
+    /// Horizontal direction of travel, `1.0` for right or `-1.0` for left
+    pub(crate) direction: Distance,
+    /// How far the fleet drops each time it reaches a side and turns around
+    pub(crate) descent_step: Distance,
+    /// Extra fraction of `horizontal_rate` the fleet lurches forward with just after dropping a
+    /// row, e.g. `0.5` for half again as fast
+    pub(crate) post_descent_speed_bonus: f64,
+    /// How long the post-descent speed bonus lasts
+    pub(crate) post_descent_bonus_ms: TimeStamp,
+    /// Time left on the current post-descent speed bonus
+    lurch_remaining: TimeStamp,
+    /// How many members the fleet had at full strength, for speeding it up as it's thinned out
+    full_count: usize,
+    /// The most the fleet can speed up from losing members, as a multiple of `horizontal_rate`
+    pub(crate) max_speedup: f64,
+    /// Decides when the fleet shoots back, and from which columns
+    pub(crate) fire: FireControl,
+    /// Speed of the fleet's bullets as they fall, in distance per millisecond
+    pub(crate) bullet_rate: f64,
+    bullets: BulletPool,
+    /// Whether bullets only hit members where their sprites are visible, see
+    /// [`Entity::collides_pixel`]
+    pub(crate) pixel_collisions: bool,
+    pub(crate) spawn_style: SpawnStyle,
+    /// Delay between each member starting to spawn and the next one along its row or column
+    pub(crate) spawn_stagger: TimeStamp,
+    /// Time since the spawn animation started, while it's still running
+    spawn_elapsed: Option<TimeStamp>,
+    /// Each column's hover, as its swing and how far it's currently lifted or lowered; empty if
+    /// the fleet doesn't hover, see [`with_bob`](Self::with_bob)
+    bob: Vec<(OffsetStrategy, Distance)>,
+    left_bound: Distance,
+    right_bound: Distance,
+    /// Grid of members, indexed `[row][column]`; destroyed members leave a `None` in their slot
+    /// so the rest of the grid keeps its layout
+    pub(crate) members: Vec<Vec<Option<Enemy>>>,
+    /// Points awarded for destroying each kind of enemy, indexed by [`Enemy::kind`]
+    pub(crate) points: Vec<u32>,
 }
 
 impl Fleet {
-    pub(crate) fn new(
-        rows: u32,
-        columns: u32,
+    /// Points for each kind of enemy unless a level says otherwise, rarer sprites scoring higher
+    pub(crate) const DEFAULT_POINTS: [u32; ENEMY_SPRITES] = [40, 30, 20, 10];
+    pub(crate) const POST_DESCENT_BONUS_MS: TimeStamp = 250.0;
+    pub(crate) const SPAWN_STAGGER_MS: TimeStamp = 60.0;
+    /// How far members hover up and down either side of their place, with [`with_bob`]
+    ///
+    /// [`with_bob`]: Self::with_bob
+    pub(crate) const BOB_AMPLITUDE: Distance = 2.0;
+    /// Time for one hover up and down and back again
+    pub(crate) const BOB_PERIOD_MS: TimeStamp = 1200.0;
+    /// How far each column's hover runs behind the one to its left, so a ripple runs along
+    pub(crate) const BOB_STAGGER_MS: TimeStamp = 150.0;
+    pub(crate) const BULLET_RATE: f64 = 0.2;
+    pub(crate) const MAX_SPEEDUP: f64 = 4.0;
+    /// Row colors for [`with_row_tints`](Self::with_row_tints), from the top row down
+    pub(crate) const ROW_TINTS: [(u8, u8, u8); 4] = [
+        (255, 96, 96),
+        (255, 200, 64),
+        (96, 255, 128),
+        (96, 192, 255),
+    ];
+    /// How long each member takes to spawn in, once its turn comes
+    const SPAWN_MS: TimeStamp = 400.0;
+
+    /// Build a fleet from a grid of enemy kinds, indexed `[row][column]`, where `None` leaves
+    /// that slot empty, laid out from (`left_bound`, `top`)
+    fn from_grid(
+        grid: Vec<Vec<Option<usize>>>,
         spacing: Distance,
         left_bound: Distance,
         right_bound: Distance,
+        top: Distance,
+        rate: f64,
     ) -> Self {
-        let mut images = icons::ENEMIES.into_iter().cycle();
-        let mut members = Vec::new();
-        for row_idx in 0..rows {
-            let mut row = Vec::new();
-            for col_idx in 0..columns {
-                let mut member = Entity::new(
-                    icons::ENEMY_WIDTH,
-                    icons::ENEMY_HEIGHT,
-                    images.next().unwrap(),
-                )
-                .expect("Block"); // TODO: dynamic size
-                member
-                    .position
-                    .set_x(Distance::from(col_idx) * (member.size().x() + spacing));
-                member
-                    .position
-                    .set_y(Distance::from(row_idx) * (member.size().y() + spacing));
-                row.push(member);
-            }
-            members.push(row);
-        }
+        let rows = grid.len();
+        let columns = grid.iter().map(Vec::len).max().unwrap_or(0);
+        let members = grid
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|kind| Some(Enemy::new(kind?)))
+                    .collect()
+            })
+            .collect();
 
-        let size = Size::new(
-            (Distance::from(columns) * (Distance::from(icons::ENEMY_WIDTH) + spacing)) - spacing,
-            (Distance::from(rows) * (Distance::from(icons::ENEMY_HEIGHT) + spacing)) - spacing,
-        );
-        let mut position = Position::new(left_bound, 60.0); // TODO: 60.0 to variable
-        position.set_offset_x(OffsetStrategy::cycle(left_bound, right_bound - size.x()));
-        Self {
-            size,
-            position,
-            rate: 0.03, // TODO
+        let mut fleet = Self {
+            size: Self::grid_size(rows, columns, spacing),
+            position: Position::default(),
+            horizontal_rate: rate,
             spacing,
+            frozen: false,
+            direction: 1.0,
+            descent_step: Enemy::size().y(),
+            post_descent_speed_bonus: 0.0,
+            post_descent_bonus_ms: Self::POST_DESCENT_BONUS_MS,
+            lurch_remaining: 0.0,
+            full_count: 0,
+            max_speedup: Self::MAX_SPEEDUP,
+            fire: FireControl::default(),
+            bullet_rate: Self::BULLET_RATE,
+            bullets: BulletPool::new(),
+            pixel_collisions: false,
+            spawn_style: SpawnStyle::None,
+            spawn_stagger: Self::SPAWN_STAGGER_MS,
+            spawn_elapsed: None,
+            bob: Vec::new(),
+            left_bound,
+            right_bound,
             members,
+            points: Self::DEFAULT_POINTS.to_vec(),
+        };
+        fleet.full_count = fleet.member_count();
+        fleet.set(left_bound, top);
+        fleet
+    }
+
+    fn grid_size(rows: usize, columns: usize, spacing: Distance) -> Size {
+        if rows == 0 || columns == 0 {
+            return Size::new(0.0, 0.0);
         }
+        let member = Enemy::size();
+        Size::new(
+            (columns as Distance * (member.x() + spacing)) - spacing,
+            (rows as Distance * (member.y() + spacing)) - spacing,
+        )
     }
 
-    pub(crate) fn animate(&mut self, context: &CanvasRenderingContext2d, offset_ts: TimeStamp) {
-        let raw_offset = offset_ts * self.rate;
-        self.offset(raw_offset, 0.0);
-        self.draw(context);
+    fn row_height(&self) -> Distance {
+        Enemy::size().y() + self.spacing
     }
-}
 
-impl XY for Fleet {
-    fn get_coordinates(&self) -> Coordinates {
-        self.position.get_coordinates()
+    /// Recompute the fleet's size after rows were added or removed, then lay the members out
+    /// again from the fleet's origin at `y`
+    fn regrid(&mut self, y: Distance) {
+        let columns = self.members.iter().map(Vec::len).max().unwrap_or(0);
+        self.size = Self::grid_size(self.members.len(), columns, self.spacing);
+        let x = self.x();
+        self.set(x, y);
     }
 
-    fn get_coordinates_mut(&mut self) -> &mut Coordinates {
-        self.position.get_coordinates_mut()
+    /// Add a row of members, one per enemy kind, above the current top row. Existing members
+    /// stay where they are on screen.
+    pub(crate) fn push_row(&mut self, kinds: &[usize]) {
+        let row = kinds.iter().map(|&kind| Some(Enemy::new(kind))).collect();
+        self.members.insert(0, row);
+        self.full_count += kinds.len();
+        let y = self.position.y() - self.row_height();
+        self.regrid(y);
     }
 
-    fn set_x(&mut self, x: Distance) {
-        for row in self.members.iter_mut() {
-            for (col_idx, member) in row.iter_mut().enumerate() {
-                let member_width = member.size().x();
-                (member as &mut dyn Rect)
-                    .set_x(((col_idx as Distance) * (member_width + self.spacing)) + x);
-            }
+    /// Remove the row at `row_idx` (counting from the top). Rows above it move down to close the
+    /// gap, so the bottom of the fleet stays put; removing the last row leaves an empty fleet.
+    pub(crate) fn remove_row(&mut self, row_idx: usize) {
+        if row_idx >= self.members.len() {
+            return;
         }
-        self.position.set_x(x);
+        let removed = self.members.remove(row_idx);
+        self.full_count = self.full_count.saturating_sub(removed.len());
+        let y = self.position.y() + self.row_height();
+        self.regrid(y);
     }
 
-    fn set_y(&mut self, y: Distance) {
-        for (row_idx, row) in self.members.iter_mut().enumerate() {
-            for member in row.iter_mut() {
-                let member_height = member.size().y();
-                (member as &mut dyn Rect)
-                    .set_y(((row_idx as Distance) * (member_height + self.spacing)) + y);
+    /// Tint each row of members with the next of `tints`, starting again from the first if
+    /// there are more rows than tints. High-contrast sprites already have their own colors and
+    /// are left alone.
+    pub(crate) fn with_row_tints(mut self, tints: &[(u8, u8, u8)]) -> Self {
+        if tints.is_empty() || graphics::high_contrast() {
+            return self;
+        }
+        for (row, &(r, g, b)) in self.members.iter_mut().zip(tints.iter().cycle()) {
+            for member in row.iter_mut().flatten() {
+                member.inner.tint(r, g, b);
             }
         }
-        self.position.set_y(y);
+        self
     }
-}
 
-impl Rect for Fleet {
-    fn position(&self) -> Position {
-        self.position
+    /// Have the members hover gently up and down as the fleet moves, each column a little behind
+    /// the last
+    pub(crate) fn with_bob(mut self) -> Self {
+        let columns = self.members.iter().map(Vec::len).max().unwrap_or(0);
+        self.bob = (0..columns)
+            .map(|col_idx| {
+                let mut strategy =
+                    OffsetStrategy::oscillate(0.0, Self::BOB_AMPLITUDE, Self::BOB_PERIOD_MS);
+                let lift = strategy.offset(0.0, -(col_idx as TimeStamp) * Self::BOB_STAGGER_MS);
+                (strategy, lift)
+            })
+            .collect();
+        let y = self.y();
+        self.set_y(y);
+        self
     }
 
-    fn position_mut(&mut self) -> &mut Position {
-        &mut self.position
+    /// Start the fleet sweeping left if `direction` is negative, or right (the default) otherwise.
+    /// A fleet sweeping left starts out against the right edge, rather than the left, so it
+    /// doesn't turn and drop a row the moment it sets off.
+    pub(crate) fn with_direction(mut self, direction: Distance) -> Self {
+        self.direction = 1.0_f64.copysign(direction);
+        if let (true, Some((_, right))) = (self.direction < 0.0, self.live_span()) {
+            let (x, y) = (self.x() + (self.right_bound - right), self.y());
+            self.set(x, y);
+        }
+        self
     }
 
-    fn size(&self) -> Size {
-        self.size
+    /// Play the spawn animation for `spawn_style` from the beginning, with every member starting
+    /// out of place
+    pub(crate) fn start_spawn(&mut self) {
+        if self.spawn_style != SpawnStyle::None {
+            self.spawn_elapsed = Some(0.0);
+            self.place_spawning(0.0);
+        }
     }
-}
 
-impl Draw for Fleet {
-    fn draw(&mut self, context: &CanvasRenderingContext2d) {
-        for row in self.members.iter_mut() {
-            for member in row.iter_mut() {
-                member.draw(context);
+    /// Whether members are still animating into place. Until they've settled the fleet holds
+    /// its position, doesn't fire, and its members shouldn't be hit.
+    pub(crate) fn is_spawning(&self) -> bool {
+        self.spawn_elapsed.is_some()
+    }
+
+    /// Lay out every member for the spawn animation `elapsed` into it, returning whether they've
+    /// all settled into place
+    fn place_spawning(&mut self, elapsed: TimeStamp) -> bool {
+        // Back to the grid slots, then each member is moved or faded from there
+        let (x, y) = (self.x(), self.y());
+        self.set(x, y);
+        let mut settled = true;
+        for (row_idx, row) in self.members.iter_mut().enumerate() {
+            for (col_idx, member) in row.iter_mut().enumerate() {
+                let Some(member) = member else { continue };
+                let delay = (row_idx + col_idx) as TimeStamp * self.spawn_stagger;
+                let progress = ((elapsed - delay) / Self::SPAWN_MS).clamp(0.0, 1.0);
+                settled &= progress >= 1.0;
+                match self.spawn_style {
+                    SpawnStyle::None => {}
+                    SpawnStyle::Fade => member.inner.opacity = progress,
+                    SpawnStyle::DropIn => {
+                        // Start just above the top of the play area, easing out into the slot
+                        let height = member.inner.size().y();
+                        let position = member.inner.position_mut();
+                        let slot_y = position.y();
+                        let fall = slot_y + height;
+                        position.set_y(slot_y - (fall * (1.0 - progress).powi(2)));
+                    }
+                }
             }
         }
+        settled
     }
-}
 
-pub(crate) struct Bullet {
-    pub(crate) inner: Entity,
-}
+    /// Move the fleet and its bullets along
+    pub(crate) fn update(&mut self, ctx: &UpdateCtx) {
+        let offset_ts = ctx.offset_ts;
+        if let (Some(elapsed), false) = (self.spawn_elapsed, self.frozen) {
+            let elapsed = elapsed + offset_ts;
+            self.spawn_elapsed = if self.place_spawning(elapsed) {
+                None
+            } else {
+                Some(elapsed)
+            };
+        } else if !self.frozen {
+            let mut rate = self.horizontal_rate * self.speedup();
+            if self.lurch_remaining > 0.0 {
+                rate *= 1.0 + self.post_descent_speed_bonus;
+                self.lurch_remaining -= offset_ts;
+            }
+            self.offset(offset_ts * rate * self.direction, 0.0);
+            if !self.bob.is_empty() {
+                for (strategy, lift) in self.bob.iter_mut() {
+                    *lift = strategy.offset(*lift, offset_ts);
+                }
+                let y = self.y();
+                self.set_y(y);
+            }
+            if self.turn_at_edge() && self.descent_step > 0.0 {
+                // Restarted rather than added to, so back-to-back turns never stack the bonus
+                self.lurch_remaining = self.post_descent_bonus_ms;
+            }
+        }
+        if !self.frozen {
+            for member in self.members.iter_mut().flatten().flatten() {
+                member.inner.advance_frame(offset_ts);
+            }
+        }
+        self.bullets.update(ctx);
+    }
 
-impl Bullet {
-    const RATE: f64 = 0.5;
+    /// The fleet's bullets in flight, to be checked for hits on the ship
+    pub(crate) fn bullets_mut(&mut self) -> &mut BulletPool {
+        &mut self.bullets
+    }
 
-    pub(crate) fn new(position: Position) -> Self {
-        let mut inner = Entity::new(icons::BULLET_WIDTH, icons::BULLET_HEIGHT, icons::BULLET).unwrap();
-        *inner.position_mut() = position;
+    /// Shoot back at the ship, whose middle is at `target`, from each member the fire pattern
+    /// picks over `offset_ts` of game time. Bullets drop from the middle of the member's bottom
+    /// edge, straight down, or toward `target` for [`FirePattern::Aimed`] as long as it's below.
+    pub(crate) fn fire(&mut self, offset_ts: TimeStamp, target: Position, rng: &mut Rng) {
+        if self.frozen {
+            return;
+        }
+        let aimed = matches!(self.fire.pattern, FirePattern::Aimed { .. });
+        for (row, col) in self.shooters(offset_ts, target, rng) {
+            let Some(member) = &self.members[row][col] else {
+                continue;
+            };
+            let origin = member.inner.position();
+            let extent = member.inner.extent();
+            let x = ((origin.x() + extent.x()) - Bullet::width()) / 2.0;
+            let muzzle = Position::new(x + (Bullet::width() / 2.0), extent.y());
+            let direction = muzzle.direction_to(target);
+            let velocity = if aimed && direction.y > 0.0 {
+                Vec2::new(
+                    direction.x * self.bullet_rate,
+                    direction.y * self.bullet_rate,
+                )
+            } else {
+                Vec2::new(0.0, self.bullet_rate)
+            };
+            self.bullets.fire(Position::new(x, extent.y()), velocity);
+        }
+    }
 
-        Self {
-            inner,
+    /// Move the sides the fleet turns around at, shifting it back inside them if its live members
+    /// now reach past either one
+    pub(crate) fn set_bounds(&mut self, left_bound: Distance, right_bound: Distance) {
+        self.left_bound = left_bound;
+        self.right_bound = right_bound;
+        let Some((left, right)) = self.live_span() else {
+            return;
+        };
+        // A fleet wider than the bounds is lined up with the left side
+        let shift = if left < left_bound {
+            left_bound - left
+        } else if right > right_bound {
+            (right_bound - right).max(left_bound - left)
+        } else {
+            return;
+        };
+        let (x, y) = (self.x(), self.y());
+        self.set(x + shift, y);
+    }
+
+    /// How many members are still alive
+    pub(crate) fn member_count(&self) -> usize {
+        self.live_members().count()
+    }
+
+    /// Which of the fleet's slots hold a live member, row by row from the top
+    pub(crate) fn live_slots(&self) -> Vec<Vec<bool>> {
+        self.members
+            .iter()
+            .map(|row| row.iter().map(Option::is_some).collect())
+            .collect()
+    }
+
+    /// Clear every slot that isn't live in `live`, as taken by [`live_slots`](Self::live_slots)
+    /// from a fleet of the same formation. The fleet still counts its full size, so it's as fast
+    /// as the thinned out one it's standing in for.
+    pub(crate) fn keep_live(&mut self, live: &[Vec<bool>]) {
+        for (row_idx, row) in self.members.iter_mut().enumerate() {
+            for (col_idx, slot) in row.iter_mut().enumerate() {
+                let is_live = live.get(row_idx).and_then(|row| row.get(col_idx));
+                if is_live != Some(&true) {
+                    *slot = None;
+                }
+            }
         }
     }
 
-    pub(crate) fn animate(&mut self, context: &CanvasRenderingContext2d, offset_ts: TimeStamp) {
-        let pos = self.inner.position_mut();
-        let y = pos.y();
-        pos.set_y(y - (Self::RATE * offset_ts));
-        self.inner.draw(context);
+    /// Multiplier on `horizontal_rate` from the fleet being thinned out, its full size over the
+    /// number left, so losing half the fleet doubles its speed, up to `max_speedup`
+    fn speedup(&self) -> f64 {
+        let remaining = self.member_count();
+        if remaining == 0 {
+            return 1.0;
+        }
+        (self.full_count as f64 / remaining as f64).clamp(1.0, self.max_speedup.max(1.0))
+    }
+
+    /// Horizontal extent `(left, right)` of the fleet's live members
+    fn live_span(&self) -> Option<(Distance, Distance)> {
+        self.live_members()
+            .map(|(_, _, member)| (member.inner.position().x(), member.inner.extent().x()))
+            .reduce(|(left, right), (x, extent)| (left.min(x), right.max(extent)))
+    }
+
+    /// If a live member has reached the side of the play area the fleet is heading toward, pull
+    /// the fleet back inside, reverse its direction, and drop it by `descent_step`. Returns
+    /// whether the fleet turned.
+    ///
+    /// Edges are found from where the live members actually are, not from the fleet's full grid,
+    /// so once the outer columns are destroyed the rest of the fleet travels further. And rather
+    /// than reflecting an overshoot back and forth between the bounds (where a long frame can
+    /// bounce several times and flip the direction an unpredictable number of times), any
+    /// overshoot is clamped to the edge, so each frame turns the fleet at most once and the
+    /// descent is applied exactly once per turn however large the frame's time step.
+    fn turn_at_edge(&mut self) -> bool {
+        let Some((left, right)) = self.live_span() else {
+            return false;
+        };
+        let correction = if self.direction > 0.0 && right >= self.right_bound {
+            self.right_bound - right
+        } else if self.direction < 0.0 && left <= self.left_bound {
+            self.left_bound - left
+        } else {
+            return false;
+        };
+
+        self.direction = -self.direction;
+        let x = self.x() + correction;
+        let y = self.y() + self.descent_step;
+        self.set(x, y);
+        true
+    }
+
+    /// Iterate over the live members of the fleet along with their `(row, column)` grid
+    /// coordinates
+    pub(crate) fn live_members(&self) -> impl Iterator<Item = (usize, usize, &Enemy)> {
+        self.members.iter().enumerate().flat_map(|(row_idx, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(col_idx, member)| Some((row_idx, col_idx, member.as_ref()?)))
+        })
+    }
+
+    /// The bottom edge of the lowest live member, if there are any left
+    pub(crate) fn bottom(&self) -> Option<Distance> {
+        self.live_members()
+            .map(|(_, _, member)| member.inner.extent().y())
+            .reduce(Distance::max)
+    }
+
+    /// Whether every member of the fleet has been destroyed
+    pub(crate) fn is_cleared(&self) -> bool {
+        self.live_members().next().is_none()
+    }
+
+    /// Hit each live member that one of `bullets` touches at game time `ts`, removing the bullet
+    /// along with it, and return the members destroyed. Members with health to spare flash and
+    /// carry on.
+    ///
+    /// A bullet overlapping two members at once only hits one, the lowest, as that's the one it
+    /// reaches first on its way up. Destroyed members leave an empty slot in their row, and
+    /// members still spawning in can't be hit.
+    pub(crate) fn take_hits(&mut self, bullets: &mut BulletPool, ts: TimeStamp) -> Vec<Enemy> {
+        if self.is_spawning() {
+            return Vec::new();
+        }
+        let mut kills = Vec::new();
+        let pixel_collisions = self.pixel_collisions;
+        bullets.retain(|bullet| {
+            let target = self
+                .live_members()
+                .filter(|(_, _, member)| {
+                    if pixel_collisions {
+                        member.inner.collides_pixel(&bullet.inner)
+                    } else {
+                        member.inner.intersects(&bullet.inner)
+                    }
+                })
+                .max_by(|(_, _, a), (_, _, b)| {
+                    a.inner.extent().y().total_cmp(&b.inner.extent().y())
+                })
+                .map(|(row, col, _)| (row, col));
+            match target {
+                Some((row, col)) => {
+                    let slot = &mut self.members[row][col];
+                    if let Some(member) = slot.as_mut() {
+                        member.health = member.health.saturating_sub(1);
+                        if member.health > 0 {
+                            member.inner.flash(ts);
+                        } else {
+                            kills.extend(slot.take());
+                        }
+                    }
+                    false
+                }
+                None => true,
+            }
+        });
+        kills
+    }
+
+    /// Find the live member whose center is closest to `to`, returning its `(row, column)` grid
+    /// coordinates and its distance from `to`
+    pub(crate) fn nearest_member(&self, to: Position) -> Option<(usize, usize, Distance)> {
+        self.live_members()
+            .map(|(row_idx, col_idx, member)| {
+                let origin = member.inner.position();
+                let extent = member.inner.extent();
+                let center = Position::new(
+                    (origin.x() + extent.x()) / 2.0,
+                    (origin.y() + extent.y()) / 2.0,
+                );
+                (row_idx, col_idx, center.distance_to(&to))
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+    }
+
+    /// The live member a shot travelling straight up between `left` and `right` would hit first,
+    /// which is the lowest of the bottom members of the columns the shot overlaps
+    pub(crate) fn first_in_line(&self, left: Distance, right: Distance) -> Option<&Enemy> {
+        let columns = self.members.iter().map(Vec::len).max().unwrap_or(0);
+        (0..columns)
+            .filter_map(|col| self.members[self.lowest_in_column(col)?][col].as_ref())
+            .filter(|member| {
+                member.inner.position().x() < right && member.inner.extent().x() > left
+            })
+            .max_by(|a, b| a.inner.extent().y().total_cmp(&b.inner.extent().y()))
+    }
+
+    /// Row index of the lowest live member in column `col`, if the column has any left
+    pub(crate) fn lowest_in_column(&self, col: usize) -> Option<usize> {
+        self.members
+            .iter()
+            .rposition(|row| matches!(row.get(col), Some(Some(_))))
+    }
+
+    /// Advance the fleet's fire pattern by `offset_ts` and return the `(row, col)` of each member
+    /// that fires in that time, always the lowest live member of its column
+    pub(crate) fn shooters(
+        &mut self,
+        offset_ts: TimeStamp,
+        target: Position,
+        rng: &mut Rng,
+    ) -> Vec<(usize, usize)> {
+        if self.is_spawning() {
+            return Vec::new();
+        }
+        let columns = self.members.iter().map(Vec::len).max().unwrap_or(0);
+        let live_columns: Vec<usize> = (0..columns)
+            .filter(|&col| self.lowest_in_column(col).is_some())
+            .collect();
+        // Aimed fire comes from the column of whichever member is nearest the ship
+        let aim = self.nearest_member(target).map(|(_, col, _)| col);
+        self.fire
+            .tick(offset_ts, &live_columns, aim, rng)
+            .into_iter()
+            .filter_map(|col| Some((self.lowest_in_column(col)?, col)))
+            .collect()
+    }
+
+    /// Points awarded for destroying an enemy of the given kind
+    pub(crate) fn points_for(&self, kind: usize) -> u32 {
+        self.points
+            .get(kind)
+            .or_else(|| Self::DEFAULT_POINTS.get(kind))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl XY for Fleet {
+    fn get_coordinates(&self) -> Coordinates {
+        self.position.get_coordinates()
+    }
+
+    fn get_coordinates_mut(&mut self) -> &mut Coordinates {
+        self.position.get_coordinates_mut()
+    }
+
+    fn set_x(&mut self, x: Distance) {
+        for row in self.members.iter_mut() {
+            for (col_idx, member) in row.iter_mut().enumerate() {
+                let Some(member) = member else { continue };
+                let member_width = member.inner.size().x();
+                (&mut member.inner as &mut dyn Rect)
+                    .set_x(((col_idx as Distance) * (member_width + self.spacing)) + x);
+            }
+        }
+        self.position.set_x(x);
+    }
+
+    fn set_y(&mut self, y: Distance) {
+        for (row_idx, row) in self.members.iter_mut().enumerate() {
+            for (col_idx, member) in row.iter_mut().enumerate() {
+                let Some(member) = member else { continue };
+                let member_height = member.inner.size().y();
+                let lift = self.bob.get(col_idx).map_or(0.0, |(_, lift)| *lift);
+                (&mut member.inner as &mut dyn Rect)
+                    .set_y(((row_idx as Distance) * (member_height + self.spacing)) + y + lift);
+            }
+        }
+        self.position.set_y(y);
+    }
+}
+
+impl Rect for Fleet {
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn position_mut(&mut self) -> &mut Position {
+        &mut self.position
+    }
+
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl Draw for Fleet {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        for row in self.members.iter_mut() {
+            for member in row.iter_mut().flatten() {
+                member.inner.draw(context);
+            }
+        }
+        self.bullets.draw(context);
+    }
+}
+
+/// Settings for a new [`Fleet`], each with a default, so only the ones that matter need setting
+///
+/// ```ignore
+/// let fleet = FleetBuilder::default().rows(3).columns(8).top(80.0).build();
+/// ```
+#[derive(Clone, Debug)]
+pub(crate) struct FleetBuilder {
+    rows: usize,
+    columns: usize,
+    /// Enemy kinds to fill the formation with, taken in turn along each row and on to the next
+    kinds: Vec<usize>,
+    /// A level's formation, in place of `rows` by `columns` of `kinds`
+    grid: Option<Vec<Vec<Option<usize>>>>,
+    /// Hits each member of a level's formation takes, laid out the same as `grid`
+    health: Vec<Vec<u32>>,
+    spacing: Distance,
+    left_bound: Distance,
+    right_bound: Distance,
+    top: Distance,
+    rate: f64,
+    points: Vec<u32>,
+}
+
+impl Default for FleetBuilder {
+    fn default() -> Self {
+        Self {
+            rows: 4,
+            columns: 6,
+            kinds: (0..ENEMY_SPRITES).collect(),
+            grid: None,
+            health: Vec::new(),
+            spacing: 48.0,
+            left_bound: 0.0,
+            right_bound: 600.0,
+            top: Self::TOP,
+            rate: 0.03,
+            points: Fleet::DEFAULT_POINTS.to_vec(),
+        }
+    }
+}
+
+impl FleetBuilder {
+    /// Where the top row starts out unless set with [`top`](Self::top)
+    pub(crate) const TOP: Distance = 60.0;
+
+    #[allow(dead_code)] // Only the benchmark changes the formation's size so far
+    pub(crate) fn rows(mut self, rows: usize) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn columns(mut self, columns: usize) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// The enemy kinds to fill the formation with, in turn. Kinds without a sprite are skipped,
+    /// and with none left every kind is used.
+    #[allow(dead_code)]
+    pub(crate) fn kinds(mut self, kinds: &[usize]) -> Self {
+        self.kinds = kinds
+            .iter()
+            .copied()
+            .filter(|&kind| kind < ENEMY_SPRITES)
+            .collect();
+        if self.kinds.is_empty() {
+            self.kinds = (0..ENEMY_SPRITES).collect();
+        }
+        self
+    }
+
+    /// Gap between neighboring members, across and down
+    pub(crate) fn spacing(mut self, spacing: Distance) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// The left and right edges the fleet sweeps between
+    pub(crate) fn bounds(mut self, left: Distance, right: Distance) -> Self {
+        self.left_bound = left;
+        self.right_bound = right;
+        self
+    }
+
+    /// Where the top row starts out
+    pub(crate) fn top(mut self, top: Distance) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// Sideways speed, in distance per millisecond, before any speedup
+    pub(crate) fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Take the formation from a level definition, along with its speed and points if it sets
+    /// them. Cells naming a sprite that doesn't exist are left empty, though
+    /// [`load_level`](crate::level::load_level) rejects those up front.
+    pub(crate) fn level(mut self, level: &LevelDef) -> Self {
+        self.grid = Some(
+            level
+                .fleet
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| {
+                            Some(cell.as_ref()?.sprite()).filter(|&kind| kind < ENEMY_SPRITES)
+                        })
+                        .collect()
+                })
+                .collect(),
+        );
+        self.health = level
+            .fleet
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.as_ref().map_or(1, CellDef::health))
+                    .collect()
+            })
+            .collect();
+        self.rate = level.fleet_rate.unwrap_or(self.rate);
+        if let Some(points) = &level.points {
+            self.points = points.clone();
+        }
+        self
+    }
+
+    pub(crate) fn build(self) -> Fleet {
+        let grid = self.grid.unwrap_or_else(|| {
+            let mut kinds = self.kinds.iter().copied().cycle();
+            (0..self.rows)
+                .map(|_| (0..self.columns).map(|_| kinds.next()).collect())
+                .collect()
+        });
+        let mut fleet = Fleet::from_grid(
+            grid,
+            self.spacing,
+            self.left_bound,
+            self.right_bound,
+            self.top,
+            self.rate,
+        );
+        fleet.points = self.points;
+        for (row, health) in fleet.members.iter_mut().zip(&self.health) {
+            for (slot, &health) in row.iter_mut().zip(health) {
+                if let Some(member) = slot {
+                    member.health = health.max(1);
+                }
+            }
+        }
+        fleet
+    }
+}
+
+/// Per-level tuning for a [`Boss`]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BossStats {
+    /// Number of hits the boss takes to defeat
+    pub(crate) health: u32,
+    /// Size of the boss relative to a regular enemy
+    pub(crate) scale: f64,
+    pub(crate) rate: f64,
+    /// Average time between the boss's shots
+    pub(crate) fire_interval: TimeStamp,
+    /// Points awarded for defeating the boss
+    pub(crate) points: u32,
+}
+
+impl Default for BossStats {
+    fn default() -> Self {
+        Self {
+            health: 20,
+            scale: 4.0,
+            rate: 0.1,
+            fire_interval: 700.0,
+            points: 1000,
+        }
+    }
+}
+
+/// A large enemy that sweeps back and forth above the play area and takes many hits, with its
+/// remaining health shown as a bar along the top of the screen
+pub(crate) struct Boss {
+    pub(crate) inner: Entity,
+    pub(crate) stats: BossStats,
+    pub(crate) health: u32,
+    /// While set, the boss holds still and holds its fire, like a frozen [`Fleet`]
+    pub(crate) frozen: bool,
+    /// Picks which of the boss's [`GUNS`](Self::GUNS) fire, and when
+    fire: FireControl,
+    bullet_rate: f64,
+    bullets: BulletPool,
+    left_bound: Distance,
+    right_bound: Distance,
+}
+
+impl Boss {
+    const HEALTH_BAR_HEIGHT: Distance = 6.0;
+    const HEALTH_BAR_Y: Distance = 8.0;
+    /// Guns spread across the bottom of the boss, firing at random
+    const GUNS: [usize; 3] = [0, 1, 2];
+    /// How far the boss bobs up and down either side of its row, and how long one bob takes
+    const HOVER: Distance = 12.0;
+    const HOVER_PERIOD_MS: TimeStamp = 2400.0;
+
+    /// A boss with its top at `y_position`, sweeping between `left_bound` and `right_bound` and
+    /// firing bullets at `bullet_rate`
+    pub(crate) fn new(
+        stats: BossStats,
+        bullet_rate: f64,
+        y_position: Distance,
+        left_bound: Distance,
+        right_bound: Distance,
+    ) -> Self {
+        let mut inner = Entity::from_png(icons::ENEMY_NODROP)
+            .unwrap()
+            .with_palette(HIGH_CONTRAST_ENEMIES[ENEMY_SPRITES - 1]);
+        inner.scale = stats.scale;
+        let width = inner.size().x();
+        let position = inner.position_mut();
+        position.set_offset_x(OffsetStrategy::cycle(left_bound, right_bound - width));
+        position.set_offset_y(OffsetStrategy::oscillate(
+            y_position,
+            Self::HOVER,
+            Self::HOVER_PERIOD_MS,
+        ));
+        position.set(
+            left_bound + ((right_bound - left_bound - width) / 2.0),
+            y_position,
+        );
+
+        Self {
+            inner,
+            stats,
+            health: stats.health,
+            frozen: false,
+            fire: FireControl::new(FirePattern::Random {
+                interval: stats.fire_interval,
+            }),
+            bullet_rate,
+            bullets: BulletPool::new(),
+            left_bound,
+            right_bound,
+        }
+    }
+
+    /// Move the sides the boss turns around at, pulling it back inside if it's now out of range
+    pub(crate) fn set_bounds(&mut self, left_bound: Distance, right_bound: Distance) {
+        self.left_bound = left_bound;
+        self.right_bound = right_bound;
+        let width = self.inner.size().x();
+        let position = self.inner.position_mut();
+        let strategy = position.offset_x();
+        position.set_offset_x(strategy.with_bounds(left_bound, right_bound - width));
+        position.offset(0.0, 0.0);
+    }
+
+    /// Take one hit at game time `ts`, returning whether the boss survived it
+    pub(crate) fn hit(&mut self, ts: TimeStamp) -> bool {
+        self.health = self.health.saturating_sub(1);
+        let alive = self.health > 0;
+        if alive {
+            self.inner.flash(ts);
+        }
+        alive
+    }
+
+    /// Take a hit at game time `ts` from each of `bullets` that touches the boss, removing those
+    /// bullets, and return whether the boss has been defeated
+    pub(crate) fn take_hits(&mut self, bullets: &mut BulletPool, ts: TimeStamp) -> bool {
+        bullets.retain(|bullet| {
+            if self.health > 0 && self.inner.intersects(&bullet.inner) {
+                self.hit(ts);
+                false
+            } else {
+                true
+            }
+        });
+        self.health == 0
+    }
+
+    /// Fire from whichever guns are due over the next `offset_ts`, unless frozen
+    pub(crate) fn fire(&mut self, offset_ts: TimeStamp, rng: &mut Rng) {
+        if self.frozen {
+            return;
+        }
+        let origin = self.inner.position();
+        let extent = self.inner.extent();
+        let spacing = (extent.x() - origin.x()) / Self::GUNS.len() as Distance;
+        for gun in self.fire.tick(offset_ts, &Self::GUNS, None, rng) {
+            let x = origin.x() + (spacing * (gun as Distance + 0.5)) - (Bullet::width() / 2.0);
+            self.bullets.fire(
+                Position::new(x, extent.y()),
+                Vec2::new(0.0, self.bullet_rate),
+            );
+        }
+    }
+
+    /// The boss's bullets in flight, to be checked for hits
+    pub(crate) fn bullets_mut(&mut self) -> &mut BulletPool {
+        &mut self.bullets
+    }
+
+    fn draw_health_bar(&self, context: &CanvasRenderingContext2d) {
+        let width = self.right_bound - self.left_bound;
+        let remaining = Distance::from(self.health) / Distance::from(self.stats.health.max(1));
+        if graphics::measuring() {
+            graphics::record(DrawnRect {
+                x: self.left_bound,
+                y: Self::HEALTH_BAR_Y,
+                width,
+                height: Self::HEALTH_BAR_HEIGHT,
+                look: remaining.to_bits(),
+            });
+            return;
+        }
+
+        context.set_fill_style_str("#400");
+        context.fill_rect(
+            self.left_bound,
+            Self::HEALTH_BAR_Y,
+            width,
+            Self::HEALTH_BAR_HEIGHT,
+        );
+        context.set_fill_style_str("#f22");
+        context.fill_rect(
+            self.left_bound,
+            Self::HEALTH_BAR_Y,
+            width * remaining,
+            Self::HEALTH_BAR_HEIGHT,
+        );
+    }
+}
+
+impl GameObject for Boss {
+    fn update(&mut self, ctx: &UpdateCtx) -> bool {
+        if !self.frozen {
+            // Hovering is measured in time rather than distance, see `OffsetStrategy::Oscillate`
+            self.inner
+                .position_mut()
+                .offset(ctx.offset_ts * self.stats.rate, ctx.offset_ts);
+        }
+        self.bullets.update(ctx);
+        self.health > 0
+    }
+}
+
+impl Draw for Boss {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        self.inner.draw(context);
+        self.bullets.draw(context);
+        self.draw_health_bar(context);
+    }
+}
+
+/// A mystery ship that crosses the top of the screen now and then, worth a random bonus if it's
+/// shot down on the way
+pub(crate) struct Ufo {
+    pub(crate) inner: Entity,
+    /// Horizontal speed in distance per millisecond, negative for travelling left
+    pub(crate) velocity: f64,
+}
+
+impl Ufo {
+    const RATE: f64 = 0.12;
+    /// Points a hit can be worth, picked from at random
+    pub(crate) const BONUSES: [u32; 3] = [50, 100, 150];
+
+    /// A UFO at height `y_position`, just out of sight past the left edge of a play area
+    /// `width` across and heading right, or past the right edge heading left
+    pub(crate) fn new(y_position: Distance, width: Distance, from_left: bool) -> Self {
+        let mut inner = Entity::from_png(icons::UFO)
+            .unwrap()
+            .with_palette(HIGH_CONTRAST_UFO);
+        let x = if from_left { -inner.size().x() } else { width };
+        let position = inner.position_mut();
+        position.set_offset_x(OffsetStrategy::linear());
+        position.set(x, y_position);
+
+        Self {
+            inner,
+            velocity: if from_left { Self::RATE } else { -Self::RATE },
+        }
+    }
+
+    /// A bonus for shooting the UFO down, one of [`BONUSES`](Self::BONUSES)
+    pub(crate) fn bonus(rng: &mut Rng) -> u32 {
+        let i = (rng.next_f64() * Self::BONUSES.len() as f64) as usize;
+        Self::BONUSES[i.min(Self::BONUSES.len() - 1)]
+    }
+
+    /// Take the first of `bullets` that touches the UFO, removing that bullet, and return
+    /// whether one did
+    pub(crate) fn take_hit(&mut self, bullets: &mut BulletPool) -> bool {
+        let hit = bullets
+            .iter()
+            .position(|bullet| self.inner.intersects(&bullet.inner));
+        match hit {
+            Some(i) => {
+                bullets.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl GameObject for Ufo {
+    fn update(&mut self, ctx: &UpdateCtx) -> bool {
+        self.inner
+            .position_mut()
+            .offset(self.velocity * ctx.offset_ts, 0.0);
+        // Alive until it's entirely past the edge it's heading for
+        if self.velocity < 0.0 {
+            self.inner.extent().x() > 0.0
+        } else {
+            self.inner.position().x() < ctx.bounds.x()
+        }
+    }
+}
+
+impl Draw for Ufo {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        self.inner.draw(context);
+    }
+}
+
+/// What catching a [`PowerUp`] does for the ship
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Boost {
+    ExtraLife,
+    /// Three bullets to a shot for a while, see [`Ship::spread_shot_until`]
+    SpreadShot,
+    /// Half the firing cooldown for a while
+    RapidFire,
+}
+
+impl Boost {
+    pub(crate) const ALL: [Boost; 3] = [Boost::ExtraLife, Boost::SpreadShot, Boost::RapidFire];
+
+    /// What the power-up's sprite is tinted, picked from the high-contrast palette so the kinds
+    /// stay easy to tell apart
+    fn color(self) -> (u8, u8, u8) {
+        match self {
+            Boost::ExtraLife => (0, 158, 115),
+            Boost::SpreadShot => (240, 228, 66),
+            Boost::RapidFire => (86, 180, 233),
+        }
+    }
+}
+
+/// A power-up dropped by a destroyed member of the fleet, falling toward the ship to be caught
+pub(crate) struct PowerUp {
+    pub(crate) inner: Entity,
+    pub(crate) boost: Boost,
+    /// Vertical speed in distance per millisecond, positive for falling down the screen
+    pub(crate) velocity: f64,
+}
+
+impl PowerUp {
+    const RATE: f64 = 0.08;
+    /// Chance of a destroyed member dropping one, with `data-power-ups`
+    pub(crate) const DROP_CHANCE: f64 = 0.1;
+
+    /// A power-up centered where `enemy` was, giving a random [`Boost`]
+    pub(crate) fn dropped_by(enemy: &Enemy, rng: &mut Rng) -> Self {
+        let i = (rng.next_f64() * Boost::ALL.len() as f64) as usize;
+        let boost = Boost::ALL[i.min(Boost::ALL.len() - 1)];
+        let mut inner = Entity::from_png(icons::POWER_UP).unwrap();
+        let (r, g, b) = boost.color();
+        inner.tint(r, g, b);
+        let (origin, extent) = (enemy.inner.position(), enemy.inner.extent());
+        let size = inner.size();
+        let position = inner.position_mut();
+        position.set_offset_xy(OffsetStrategy::linear());
+        position.set(
+            ((origin.x() + extent.x()) - size.x()) / 2.0,
+            ((origin.y() + extent.y()) - size.y()) / 2.0,
+        );
+
+        Self {
+            inner,
+            boost,
+            velocity: Self::RATE,
+        }
+    }
+}
+
+impl GameObject for PowerUp {
+    fn update(&mut self, ctx: &UpdateCtx) -> bool {
+        self.inner
+            .position_mut()
+            .add(0.0, self.velocity * ctx.offset_ts);
+        // Alive until it's fallen entirely off the bottom of the play area
+        self.inner.position().y() < ctx.bounds.y()
+    }
+}
+
+impl Draw for PowerUp {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        self.inner.draw(context);
+    }
+}
+
+/// Fading copies of a sprite drawn at its last few positions
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Trail {
+    /// Number of previous positions kept and drawn
+    pub(crate) length: usize,
+    /// Opacity of each copy relative to the one in front of it, so `0.5` halves it every step
+    pub(crate) fade: f64,
+}
+
+impl Trail {
+    pub(crate) const DEFAULT_FADE: f64 = 0.5;
+}
+
+pub(crate) struct Bullet {
+    pub(crate) inner: Entity,
+    /// In distance per millisecond, with negative `y` travelling up the screen
+    pub(crate) velocity: Vec2,
+    pub(crate) trail: Option<Trail>,
+    /// Recent positions, newest first, never more than the trail's length
+    history: VecDeque<Position>,
+}
+
+impl Bullet {
+    /// Width of a bullet's sprite, for lining it up with the gun it comes out of
+    pub(crate) fn width() -> Distance {
+        Distance::from(icons::size(icons::BULLET).0)
+    }
+
+    pub(crate) fn new(position: Position, velocity: Vec2) -> Self {
+        let mut inner = Entity::from_png(icons::BULLET)
+            .unwrap()
+            .with_palette(HIGH_CONTRAST_BULLET);
+        *inner.position_mut() = position;
+
+        Self {
+            inner,
+            velocity,
+            trail: None,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Make a spent bullet as good as new, at `position` and moving at `velocity`
+    fn reset(&mut self, position: Position, velocity: Vec2) {
+        *self.inner.position_mut() = position;
+        self.inner.reset_pixels();
+        self.inner.opacity = 1.0;
+        self.inner.flash_until = None;
+        self.velocity = velocity;
+        self.trail = None;
+        self.history.clear();
+    }
+}
+
+/// The bullets one side has in flight, along with spent ones kept for reuse
+///
+/// Bullets share their sprite's pixels, but each still has an entity of its own, with the image
+/// and canvases cached to draw it and the positions its trail follows. So rather than being
+/// dropped when they hit something or leave the screen, bullets are set aside and fired again
+/// later. Past the first few shots, firing stops allocating at all.
+#[derive(Default)]
+pub(crate) struct BulletPool {
+    active: Vec<Bullet>,
+    spent: Vec<Bullet>,
+}
+
+impl BulletPool {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put a bullet in flight at `position`, reusing a spent one if there is one
+    pub(crate) fn fire(&mut self, position: Position, velocity: Vec2) -> &mut Bullet {
+        let bullet = match self.spent.pop() {
+            Some(mut bullet) => {
+                bullet.reset(position, velocity);
+                bullet
+            }
+            None => Bullet::new(position, velocity),
+        };
+        self.active.push(bullet);
+        self.active.last_mut().unwrap()
+    }
+
+    /// Number of bullets in flight
+    pub(crate) fn len(&self) -> usize {
+        self.active.len()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Bullet> {
+        self.active.iter()
+    }
+
+    /// Keep only the bullets in flight that `keep` returns `true` for, in order, setting the rest
+    /// aside for reuse
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&Bullet) -> bool) {
+        let mut i = 0;
+        while i < self.active.len() {
+            if keep(&self.active[i]) {
+                i += 1;
+            } else {
+                self.spent.push(self.active.remove(i));
+            }
+        }
+    }
+
+    /// Take the `i`th bullet in flight out of play
+    pub(crate) fn remove(&mut self, i: usize) {
+        self.spent.push(self.active.remove(i));
+    }
+
+    /// Move every bullet in flight along, setting aside the ones that have left the screen
+    pub(crate) fn update(&mut self, ctx: &UpdateCtx) {
+        let mut i = 0;
+        while i < self.active.len() {
+            if self.active[i].update(ctx) {
+                i += 1;
+            } else {
+                self.remove(i);
+            }
+        }
+    }
+}
+
+impl Draw for BulletPool {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        graphics::draw_all(&mut self.active, context);
+    }
+}
+
+impl GameObject for Bullet {
+    fn update(&mut self, ctx: &UpdateCtx) -> bool {
+        if let Some(trail) = self.trail {
+            self.history.push_front(self.inner.position());
+            self.history.truncate(trail.length);
+        }
+        self.inner.position_mut().add(
+            self.velocity.x * ctx.offset_ts,
+            self.velocity.y * ctx.offset_ts,
+        );
+        // Alive until it's entirely off any edge of the play area
+        let (position, extent) = (self.inner.position(), self.inner.extent());
+        extent.x() > 0.0
+            && extent.y() > 0.0
+            && position.x() < ctx.bounds.x()
+            && position.y() < ctx.bounds.y()
+    }
+}
+
+impl Draw for Bullet {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        if let Some(trail) = self.trail {
+            let head = self.inner.position();
+            // Oldest first, so newer, more opaque copies land on top
+            for (age, position) in self.history.iter().enumerate().rev() {
+                *self.inner.position_mut() = *position;
+                self.inner.opacity = trail.fade.powi(age as i32 + 1);
+                self.inner.draw(context);
+            }
+            *self.inner.position_mut() = head;
+            self.inner.opacity = 1.0;
+        }
+        self.inner.draw(context);
+    }
+}
+
+/// One speck of an [`Explosion`]
+struct Particle {
+    position: Position,
+    /// In distance per millisecond
+    velocity: Vec2,
+}
+
+/// A burst of particles flying out from where a member of the fleet was destroyed, fading away
+/// over a fraction of a second
+pub(crate) struct Explosion {
+    particles: Vec<Particle>,
+    color: (u8, u8, u8),
+    elapsed: TimeStamp,
+}
+
+impl Explosion {
+    const PARTICLES: usize = 12;
+    const LIFETIME_MS: TimeStamp = 400.0;
+    /// Fastest a particle flies out, in distance per millisecond
+    const MAX_SPEED: f64 = 0.12;
+    const PARTICLE_SIZE: Distance = 2.0;
+
+    /// An explosion centered on `enemy`, in its high-contrast color if that's on
+    pub(crate) fn at(enemy: &Enemy, rng: &mut Rng) -> Self {
+        let origin = enemy.inner.position();
+        let extent = enemy.inner.extent();
+        let center = Position::new(
+            ((origin.x() + extent.x()) - Self::PARTICLE_SIZE) / 2.0,
+            ((origin.y() + extent.y()) - Self::PARTICLE_SIZE) / 2.0,
+        );
+        let particles = (0..Self::PARTICLES)
+            .map(|_| {
+                let angle = rng.range(0.0, std::f64::consts::TAU);
+                let speed = rng.range(Self::MAX_SPEED / 4.0, Self::MAX_SPEED);
+                Particle {
+                    position: center,
+                    velocity: Vec2::new(angle.cos() * speed, angle.sin() * speed),
+                }
+            })
+            .collect();
+        let color = if graphics::high_contrast() {
+            HIGH_CONTRAST_ENEMIES[enemy.kind]
+        } else {
+            (255, 255, 255)
+        };
+
+        Self {
+            particles,
+            color,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl GameObject for Explosion {
+    fn update(&mut self, ctx: &UpdateCtx) -> bool {
+        self.elapsed += ctx.offset_ts;
+        for particle in self.particles.iter_mut() {
+            particle.position.offset(
+                particle.velocity.x * ctx.offset_ts,
+                particle.velocity.y * ctx.offset_ts,
+            );
+        }
+        self.elapsed < Self::LIFETIME_MS
+    }
+}
+
+impl Draw for Explosion {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        let alpha = (1.0 - (self.elapsed / Self::LIFETIME_MS)).clamp(0.0, 1.0);
+        if graphics::measuring() {
+            // Particles only ever fly apart, so one box around all of them is as good as any
+            let (mut left, mut top) = (Distance::INFINITY, Distance::INFINITY);
+            let (mut right, mut bottom) = (Distance::NEG_INFINITY, Distance::NEG_INFINITY);
+            for particle in &self.particles {
+                left = left.min(particle.position.x());
+                top = top.min(particle.position.y());
+                right = right.max(particle.position.x() + Self::PARTICLE_SIZE);
+                bottom = bottom.max(particle.position.y() + Self::PARTICLE_SIZE);
+            }
+            if left < right {
+                graphics::record(DrawnRect {
+                    x: left,
+                    y: top,
+                    width: right - left,
+                    height: bottom - top,
+                    look: alpha.to_bits(),
+                });
+            }
+            return;
+        }
+        let (r, g, b) = self.color;
+        context.set_fill_style_str(&format!("rgba({}, {}, {}, {})", r, g, b, alpha));
+        for particle in &self.particles {
+            context.fill_rect(
+                particle.position.x(),
+                particle.position.y(),
+                Self::PARTICLE_SIZE,
+                Self::PARTICLE_SIZE,
+            );
+        }
+    }
+}
+
+/// A bunker the ship can shelter behind, worn away a little by every bullet that hits it, from
+/// either side
+pub(crate) struct Barrier {
+    pub(crate) inner: Entity,
+}
+
+impl Barrier {
+    const WIDTH: u32 = 44;
+    const HEIGHT: u32 = 32;
+    const COLOR: (u8, u8, u8) = (32, 255, 32);
+    /// How far in the top corners are cut away, at the very top
+    const CORNER: u32 = 8;
+    /// Size of the opening in the middle of the bottom edge
+    const ARCH_WIDTH: u32 = 16;
+    const ARCH_HEIGHT: u32 = 10;
+    /// Radius of the hole a bullet knocks out where it hits
+    const EROSION_RADIUS: Distance = 3.0;
+
+    pub(crate) fn new() -> Self {
+        let (width, height) = (Self::WIDTH, Self::HEIGHT);
+        let (r, g, b) = Self::COLOR;
+        let mut data = vec![0; (width * height * 4) as usize];
+        for (i, pixel) in data.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            let corner = Self::CORNER.saturating_sub(y);
+            let in_corner = x < corner || x >= width - corner;
+            let in_arch =
+                y >= height - Self::ARCH_HEIGHT && (2 * x).abs_diff(width) < Self::ARCH_WIDTH;
+            if !in_corner && !in_arch {
+                pixel.copy_from_slice(&[r, g, b, u8::MAX]);
+            }
+        }
+        let inner = Entity::new(width, height, data)
+            .unwrap()
+            .with_palette(HIGH_CONTRAST_BARRIER);
+        Self { inner }
+    }
+
+    /// Stand `barriers` on `bottom`, each centered on its own x position in `centers` (like a
+    /// level's bunkers), or spaced evenly between `left_bound` and `right_bound` if there isn't
+    /// one for it
+    pub(crate) fn arrange(
+        barriers: &mut [Barrier],
+        centers: &[Distance],
+        left_bound: Distance,
+        right_bound: Distance,
+        bottom: Distance,
+    ) {
+        let spacing = (right_bound - left_bound) / barriers.len() as Distance;
+        for (i, barrier) in barriers.iter_mut().enumerate() {
+            let size = barrier.inner.size();
+            let center = centers
+                .get(i)
+                .copied()
+                .unwrap_or(left_bound + (spacing * (i as Distance + 0.5)));
+            let position = barrier.inner.position_mut();
+            position.set_x(center - (size.x() / 2.0));
+            position.set_y(bottom - size.y());
+        }
+    }
+
+    /// Stop each of `bullets` that runs into what's left of the barrier, removing those bullets
+    /// and eroding the barrier where they hit. Bullets pass through holes already worn away.
+    pub(crate) fn take_hits(&mut self, bullets: &mut BulletPool) {
+        bullets.retain(|bullet| match self.impact(bullet) {
+            Some((x, y)) => {
+                self.inner.clear_circle(x, y, Self::EROSION_RADIUS);
+                false
+            }
+            None => true,
+        });
+    }
+
+    /// Where `bullet` meets the barrier, in the barrier's sprite pixels: the first solid pixel
+    /// under the bullet along its path, so the lowest for a bullet going up and the highest for
+    /// one coming down
+    fn impact(&self, bullet: &Bullet) -> Option<(Distance, Distance)> {
+        if !self.inner.intersects(&bullet.inner) {
+            return None;
+        }
+        let origin = self.inner.position();
+        let (start, end) = (bullet.inner.position(), bullet.inner.extent());
+        let width = self.inner.size.x() as usize;
+        let clip = |start: Distance, end: Distance, limit: usize| {
+            (start.floor().max(0.0) as usize)..(end.ceil().max(0.0) as usize).min(limit)
+        };
+        let columns = clip(start.x() - origin.x(), end.x() - origin.x(), width);
+        let height = self.inner.size.y() as usize;
+        let mut rows = clip(start.y() - origin.y(), end.y() - origin.y(), height);
+        let solid = |row: usize| {
+            columns
+                .clone()
+                .find(|column| self.inner.data[((row * width) + column) * 4 + 3] != 0)
+                .map(|column| (column as Distance + 0.5, row as Distance + 0.5))
+        };
+        if bullet.velocity.y < 0.0 {
+            rows.rev().find_map(solid)
+        } else {
+            rows.find_map(solid)
+        }
+    }
+}
+
+impl Draw for Barrier {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        self.inner.draw(context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen::JsCast;
+
+    use super::*;
+    use crate::game::Input;
+    use crate::score::Score;
+
+    const STILL: Input = Input {
+        left: false,
+        right: false,
+        up: false,
+        down: false,
+    };
+    const LEFT: Input = Input {
+        left: true,
+        ..STILL
+    };
+    const RIGHT: Input = Input {
+        right: true,
+        ..STILL
+    };
+
+    fn ctx(offset_ts: TimeStamp) -> UpdateCtx<'static> {
+        steer(offset_ts, &STILL)
+    }
+
+    /// An update `offset_ts` long with the controls in `input` held down
+    fn steer(offset_ts: TimeStamp, input: &Input) -> UpdateCtx<'_> {
+        UpdateCtx {
+            ts: 0.0,
+            offset_ts,
+            input,
+            bounds: Size::new(600.0, 480.0),
+        }
+    }
+
+    fn ship() -> Ship {
+        Ship::new(0.5, 0.5, 440.0, 0.0, 0.0, 600.0)
+    }
+
+    #[test]
+    fn ship_moves_along_its_heading_and_stops_at_the_edge() {
+        let mut ship = ship();
+        let width = ship.inner.size().x();
+        let start = ship.inner.position().x();
+        ship.update(&steer(100.0, &RIGHT));
+        assert_eq!(ship.inner.position().x(), start + 50.0);
+
+        ship.update(&steer(10_000.0, &RIGHT));
+        assert_eq!(ship.inner.position().x(), 600.0 - width);
+
+        ship.update(&steer(10.0, &LEFT));
+        assert_eq!(ship.inner.position().x(), 600.0 - width - 5.0);
+    }
+
+    #[test]
+    fn ship_stays_on_its_row_without_free_move() {
+        let mut ship = ship();
+        ship.update(&ctx(16.0));
+        let y = ship.inner.position().y();
+        let up = Input { up: true, ..STILL };
+        ship.update(&steer(100.0, &up));
+        assert_eq!(ship.inner.position().y(), y);
+    }
+
+    #[test]
+    fn bullet_destroys_the_member_it_touches() {
+        let mut fleet = FleetBuilder::default().build();
+        let members = fleet.live_members().count();
+        let (row, col, target) = fleet.live_members().last().unwrap();
+        let position = target.inner.position();
+        let mut bullets = BulletPool::new();
+        bullets.fire(position, Vec2::new(0.0, -0.5));
+
+        let kills = fleet.take_hits(&mut bullets, 0.0);
+        assert_eq!(kills.len(), 1);
+        assert_eq!(bullets.len(), 0);
+        assert!(fleet.members[row][col].is_none());
+        assert_eq!(fleet.live_members().count(), members - 1);
+    }
+
+    #[test]
+    fn level_health_lets_members_take_extra_hits() {
+        let level = crate::level::load_level(
+            r#"{ "fleet": [[0, { "sprite": 1, "health": 2 }, { "sprite": 2 }]] }"#,
+        )
+        .unwrap();
+        let mut fleet = FleetBuilder::default().level(&level).build();
+        let health: Vec<u32> = fleet.live_members().map(|(_, _, m)| m.health).collect();
+        assert_eq!(health, [1, 2, 1]);
+
+        let position = fleet.members[0][1].as_ref().unwrap().inner.position();
+        let mut bullets = BulletPool::new();
+        bullets.fire(position, Vec2::new(0.0, -0.5));
+        assert!(fleet.take_hits(&mut bullets, 0.0).is_empty());
+        assert_eq!(bullets.len(), 0);
+        assert_eq!(fleet.members[0][1].as_ref().unwrap().health, 1);
+
+        bullets.fire(position, Vec2::new(0.0, -0.5));
+        let kills = fleet.take_hits(&mut bullets, 100.0);
+        assert_eq!(
+            kills.iter().map(|enemy| enemy.kind).collect::<Vec<_>>(),
+            [1]
+        );
+        assert!(fleet.members[0][1].is_none());
+    }
+
+    #[test]
+    fn bullet_in_a_gap_misses() {
+        let mut fleet = FleetBuilder::default().build();
+        let mut bullets = BulletPool::new();
+        // Between the first two columns, below the bottom row
+        bullets.fire(Position::new(0.0, 400.0), Vec2::new(0.0, -0.5));
+
+        assert!(fleet.take_hits(&mut bullets, 0.0).is_empty());
+        assert_eq!(bullets.len(), 1);
+    }
+
+    #[test]
+    fn destroyed_members_score_by_kind() {
+        let mut fleet = FleetBuilder::default().build();
+        let (_, _, target) = fleet.live_members().next().unwrap();
+        let position = target.inner.position();
+        let mut bullets = BulletPool::new();
+        bullets.fire(position, Vec2::new(0.0, -0.5));
+
+        let mut score = Score::new(Position::default());
+        for enemy in fleet.take_hits(&mut bullets, 0.0) {
+            score.add(fleet.points_for(enemy.kind));
+        }
+        assert_eq!(score.points, Fleet::DEFAULT_POINTS[0]);
+    }
+
+    #[test]
+    fn fleet_speeds_up_as_members_are_destroyed() {
+        let mut fleet = FleetBuilder::default().build();
+        fleet.max_speedup = 8.0;
+        let mut last = fleet.speedup();
+        assert_eq!(last, 1.0);
+        // Down to the last member, as an empty fleet doesn't move at all
+        while fleet.member_count() > 1 {
+            let (row, col, _) = fleet.live_members().next().unwrap();
+            fleet.members[row][col] = None;
+            let speedup = fleet.speedup();
+            assert!(speedup >= last, "{} after {}", speedup, last);
+            last = speedup;
+        }
+        assert_eq!(last, 8.0);
+    }
+
+    #[test]
+    fn fleet_sweeping_left_starts_at_the_right_and_turns_at_the_left() {
+        let mut fleet = FleetBuilder::default().build().with_direction(-1.0);
+        let (left, right) = fleet.live_span().unwrap();
+        assert_eq!(right, 600.0);
+        let y = fleet.y();
+
+        fleet.update(&ctx(16.0));
+        assert_eq!(fleet.direction, -1.0);
+        assert_eq!(fleet.y(), y);
+        assert!(fleet.live_span().unwrap().1 < right);
+
+        // Crossing the whole play area, it turns once on reaching the left edge
+        let mut turns = 0;
+        while turns == 0 {
+            fleet.update(&ctx(16.0));
+            turns += usize::from(fleet.direction > 0.0);
+            assert!(fleet.live_span().unwrap().0 >= 0.0);
+        }
+        assert_eq!(fleet.live_span().unwrap().0, 0.0);
+        assert_eq!(fleet.y(), y + fleet.descent_step);
+        assert!(left > 0.0);
+    }
+
+    /// Where each live member is, by its grid coordinates
+    fn member_positions(fleet: &Fleet) -> Vec<(usize, usize, Distance, Distance)> {
+        fleet
+            .live_members()
+            .map(|(row, col, member)| {
+                let position = member.inner.position();
+                (row, col, position.x(), position.y())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pushed_row_joins_above_the_top_row() {
+        let mut fleet = FleetBuilder::default().top(200.0).build();
+        let before = member_positions(&fleet);
+        let (members, height) = (fleet.member_count(), fleet.size.y());
+        let row_height = Enemy::size().y() + fleet.spacing;
+        fleet.push_row(&[1; 6]);
+
+        assert_eq!(fleet.member_count(), members + 6);
+        assert_eq!(fleet.size.y(), height + row_height);
+        assert_eq!(fleet.y(), 200.0 - row_height);
+        let after = member_positions(&fleet);
+        // Everyone already there stays put, one row further down the grid
+        for &(row, col, x, y) in &before {
+            assert!(after.contains(&(row + 1, col, x, y)));
+        }
+        for &(row, col, x, y) in after.iter().filter(|(row, ..)| *row == 0) {
+            assert_eq!(fleet.members[row][col].as_ref().unwrap().kind, 1);
+            assert!(before.contains(&(0, col, x, y + row_height)));
+        }
+    }
+
+    #[test]
+    fn removed_row_closes_up_from_above() {
+        let mut fleet = FleetBuilder::default().rows(3).build();
+        let before = member_positions(&fleet);
+        let (members, height, bottom) = (fleet.member_count(), fleet.size.y(), fleet.bottom());
+        let row_height = Enemy::size().y() + fleet.spacing;
+        fleet.remove_row(1);
+
+        assert_eq!(fleet.member_count(), members - 6);
+        assert_eq!(fleet.size.y(), height - row_height);
+        assert_eq!(fleet.bottom(), bottom);
+        let after = member_positions(&fleet);
+        for &(_, col, x, y) in before.iter().filter(|(row, ..)| *row == 0) {
+            assert!(after.contains(&(0, col, x, y + row_height)));
+        }
+        for &(_, col, x, y) in before.iter().filter(|(row, ..)| *row == 2) {
+            assert!(after.contains(&(1, col, x, y)));
+        }
+
+        // Past the end there's nothing to remove, and removing every row leaves it cleared
+        fleet.remove_row(2);
+        assert_eq!(fleet.member_count(), members - 6);
+        fleet.remove_row(0);
+        fleet.remove_row(0);
+        assert!(fleet.is_cleared());
+        assert_eq!(fleet.size.y(), 0.0);
+    }
+
+    #[test]
+    fn removing_a_row_shrinks_the_full_fleet() {
+        let mut fleet = FleetBuilder::default().build();
+        fleet.push_row(&[0; 6]);
+        fleet.remove_row(0);
+        fleet.remove_row(0);
+        assert_eq!(fleet.speedup(), 1.0);
+    }
+
+    #[test]
+    fn reused_bullets_keep_the_pool_the_same_size() {
+        let mut pool = BulletPool::new();
+        let volley = |pool: &mut BulletPool| {
+            for i in 0..20 {
+                pool.fire(
+                    Position::new(f64::from(i) * 20.0, 400.0),
+                    Vec2::new(0.0, -0.5),
+                );
+            }
+            // Half are culled by hitting something, and the rest by leaving the screen
+            let mut hit = false;
+            pool.retain(|_| {
+                hit = !hit;
+                !hit
+            });
+            pool.update(&ctx(10_000.0));
+            assert_eq!(pool.len(), 0);
+        };
+        volley(&mut pool);
+
+        // Every bullet comes back to be reused, so neither list has to grow after the first volley
+        let sizes = |pool: &BulletPool| {
+            (
+                pool.spent.len(),
+                pool.active.capacity(),
+                pool.spent.capacity(),
+            )
+        };
+        let before = sizes(&pool);
+        assert_eq!(before.0, 20);
+        for _ in 0..10 {
+            volley(&mut pool);
+            assert_eq!(sizes(&pool), before);
+        }
+    }
+
+    #[test]
+    fn bullets_share_their_sprite() {
+        let mut pool = BulletPool::new();
+        pool.fire(Position::default(), Vec2::default());
+        pool.fire(Position::default(), Vec2::default());
+        let icon = icons::shared(icons::BULLET);
+        assert!(pool
+            .iter()
+            .all(|bullet| Rc::ptr_eq(&bullet.inner.data, &icon.pixels)));
+    }
+
+    #[test]
+    fn animation_advances_and_wraps() {
+        let frames: Vec<Rc<[u8]>> = (0..3u8).map(|i| Rc::from(vec![i; 4])).collect();
+        let mut entity = Entity::new_animated(1, 1, frames, 100.0).unwrap();
+        assert_eq!((entity.frame, &entity.data[..]), (0, &[0; 4][..]));
+
+        entity.advance_frame(60.0);
+        assert_eq!(entity.frame, 0);
+        entity.advance_frame(60.0);
+        assert_eq!((entity.frame, &entity.data[..]), (1, &[1; 4][..]));
+        entity.advance_frame(100.0);
+        assert_eq!((entity.frame, &entity.data[..]), (2, &[2; 4][..]));
+        entity.advance_frame(100.0);
+        assert_eq!((entity.frame, &entity.data[..]), (0, &[0; 4][..]));
+        // A long step skips ahead as many frames as it covers
+        entity.advance_frame(400.0);
+        assert_eq!((entity.frame, &entity.data[..]), (1, &[1; 4][..]));
+    }
+
+    #[test]
+    fn still_sprites_dont_animate() {
+        let mut entity = Entity::new(1, 1, vec![7; 4]).unwrap();
+        entity.advance_frame(1000.0);
+        assert_eq!((entity.frame, &entity.data[..]), (0, &[7; 4][..]));
+    }
+
+    #[test]
+    fn fleet_members_shuffle_while_it_moves() {
+        let mut fleet = FleetBuilder::default().build();
+        let pose = |fleet: &Fleet| {
+            let (_, _, member) = fleet.live_members().next().unwrap();
+            (member.inner.frame, member.inner.data.to_vec())
+        };
+        let (frame, first) = pose(&fleet);
+        assert_eq!(frame, 0);
+
+        fleet.update(&ctx(Enemy::FRAME_MS));
+        let (frame, second) = pose(&fleet);
+        assert_eq!(frame, 1);
+        assert_ne!(first, second);
+
+        fleet.frozen = true;
+        fleet.update(&ctx(Enemy::FRAME_MS));
+        assert_eq!(pose(&fleet), (1, second));
+    }
+
+    #[test]
+    fn flipping_twice_restores_the_sprite() {
+        let icon = icons::shared(icons::UFO);
+        let mut entity = Entity::from_png(icons::UFO).unwrap();
+        entity.flip_horizontal();
+        assert_eq!(entity.data, icons::mirrored(icons::UFO).pixels);
+        entity.flip_horizontal();
+        assert_eq!(entity.data, icon.pixels);
+    }
+
+    #[test]
+    fn flipping_mirrors_every_frame() {
+        let frames = vec![Rc::from(vec![1, 1, 1, 1, 2, 2, 2, 2]), Rc::from(vec![3; 8])];
+        let mut entity = Entity::new_animated(2, 1, frames, 100.0).unwrap();
+        entity.flip_horizontal();
+        assert_eq!(&entity.data[..], [2, 2, 2, 2, 1, 1, 1, 1]);
+        entity.advance_frame(100.0);
+        entity.advance_frame(100.0);
+        assert_eq!(&entity.data[..], [2, 2, 2, 2, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn ship_faces_the_way_it_moves() {
+        let mut ship = ship();
+        let icon = icons::shared(icons::SHIP);
+        let mirrored = icons::mirrored(icons::SHIP);
+        ship.update(&steer(16.0, &LEFT));
+        assert_eq!(ship.inner.data, icon.pixels);
+
+        ship.update(&steer(16.0, &RIGHT));
+        assert_eq!(ship.inner.data, mirrored.pixels);
+        // Stopping leaves it facing the same way
+        ship.update(&ctx(16.0));
+        assert_eq!(ship.inner.data, mirrored.pixels);
+
+        ship.update(&steer(16.0, &LEFT));
+        assert_eq!(ship.inner.data, icon.pixels);
+    }
+
+    #[test]
+    fn wrapping_ship_keeps_leaning_and_facing_the_way_it_moves() {
+        let mut ship = ship();
+        ship.set_wrap(true);
+        let mirrored = icons::mirrored(icons::SHIP);
+        let mut wrapped = false;
+        for _ in 0..200 {
+            let x = ship.inner.position().x();
+            ship.update(&steer(16.0, &RIGHT));
+            assert!(ship.inner.angle > 0.0);
+            assert_eq!(ship.inner.data, mirrored.pixels);
+            if ship.inner.position().x() < x {
+                wrapped = true;
+                break;
+            }
+        }
+        assert!(wrapped);
+    }
+
+    #[test]
+    fn ship_knows_when_it_is_pinned_against_an_edge() {
+        let mut ship = ship();
+        assert!(!ship.at_left_bound() && !ship.at_right_bound());
+        ship.update(&steer(10_000.0, &LEFT));
+        assert!(ship.at_left_bound() && !ship.at_right_bound());
+        ship.update(&steer(10_000.0, &RIGHT));
+        assert!(!ship.at_left_bound() && ship.at_right_bound());
+        ship.update(&steer(16.0, &LEFT));
+        assert!(!ship.at_left_bound() && !ship.at_right_bound());
+    }
+
+    #[test]
+    fn gliding_ship_is_pinned_once_it_comes_to_rest_at_an_edge() {
+        let mut ship = ship();
+        ship.set_glide(0.5);
+        ship.update(&steer(16.0, &RIGHT));
+        assert!(!ship.at_right_bound());
+        for _ in 0..100 {
+            ship.update(&steer(16.0, &RIGHT));
+        }
+        assert!(ship.at_right_bound());
+        for _ in 0..100 {
+            ship.update(&steer(16.0, &LEFT));
+        }
+        assert!(ship.at_left_bound() && !ship.at_right_bound());
+    }
+
+    #[test]
+    fn wrapping_ship_is_never_pinned() {
+        let mut ship = ship();
+        ship.set_wrap(true);
+        ship.inner.position_mut().set_x(0.0);
+        assert!(!ship.at_left_bound());
+        for _ in 0..100 {
+            ship.update(&steer(16.0, &RIGHT));
+            assert!(!ship.at_left_bound() && !ship.at_right_bound());
+        }
+    }
+
+    #[test]
+    fn ship_starts_in_the_middle_of_its_bounds() {
+        for (left, right) in [(0.0, 600.0), (24.0, 576.0), (100.0, 149.0)] {
+            let ship = Ship::new(0.5, 0.5, 440.0, 0.0, left, right);
+            let width = ship.inner.size().x();
+            let x = ship.inner.position().x();
+            assert_eq!(x, left + ((right - left) / 2.0) - (width / 2.0));
+            // The same space either side
+            assert_eq!(x - left, right - (x + width));
+        }
+    }
+
+    #[test]
+    fn fleet_rows_start_from_its_top() {
+        for top in [FleetBuilder::TOP, 0.0, 125.5] {
+            let fleet = FleetBuilder::default().spacing(10.0).top(top).build();
+            let row_height = Enemy::size().y() + 10.0;
+            for (row, _, member) in fleet.live_members() {
+                assert_eq!(
+                    member.inner.position().y(),
+                    top + (row as Distance * row_height)
+                );
+            }
+        }
+    }
+
+    /// The center of the member at (`row`, `col`)
+    fn center_of(fleet: &Fleet, row: usize, col: usize) -> Position {
+        let member = &fleet.members[row][col].as_ref().unwrap().inner;
+        let (origin, extent) = (member.position(), member.extent());
+        Position::new(
+            (origin.x() + extent.x()) / 2.0,
+            (origin.y() + extent.y()) / 2.0,
+        )
+    }
+
+    #[test]
+    fn nearest_member_skips_destroyed_ones() {
+        let mut fleet = FleetBuilder::default().build();
+        let at = center_of(&fleet, 1, 2);
+        assert_eq!(fleet.nearest_member(at), Some((1, 2, 0.0)));
+
+        fleet.members[1][2] = None;
+        let (row, col, distance) = fleet.nearest_member(at).unwrap();
+        assert_ne!((row, col), (1, 2));
+        assert_eq!(distance, center_of(&fleet, row, col).distance_to(&at));
+        // Whichever neighbor it picks is as close as any other live member
+        assert!(fleet
+            .live_members()
+            .all(|(r, c, _)| center_of(&fleet, r, c).distance_to(&at) >= distance));
+
+        fleet
+            .members
+            .iter_mut()
+            .flatten()
+            .for_each(|slot| *slot = None);
+        assert_eq!(fleet.nearest_member(at), None);
+    }
+
+    #[test]
+    fn aimed_fire_comes_from_the_column_nearest_the_ship() {
+        let mut fleet = FleetBuilder::default().build();
+        fleet.fire = FireControl::new(FirePattern::Aimed { interval: 100.0 });
+        let mut rng = Rng::new(1);
+        for col in [0, 4] {
+            let below = center_of(&fleet, 3, col);
+            let target = Position::new(below.x(), 440.0);
+            let shooters = fleet.shooters(300.0, target, &mut rng);
+            assert_eq!(shooters, vec![(3, col); 3]);
+        }
+    }
+
+    #[test]
+    fn aimed_bullets_head_for_the_ship() {
+        let mut fleet = FleetBuilder::default().build();
+        fleet.fire = FireControl::new(FirePattern::Aimed { interval: 100.0 });
+        // Off past the fleet's right end, so every member has to shoot to its right
+        let target = Position::new(fleet.extent().x() + 100.0, fleet.extent().y() + 300.0);
+        fleet.fire(100.0, target, &mut Rng::new(1));
+
+        let bullet = fleet.bullets.iter().next().unwrap();
+        assert!(bullet.velocity.x > 0.0);
+        assert!((bullet.velocity.length() - fleet.bullet_rate).abs() < 1e-9);
+        let muzzle = Position::new(
+            bullet.inner.position().x() + (Bullet::width() / 2.0),
+            bullet.inner.position().y(),
+        );
+        let heading = muzzle.direction_to(target);
+        let direction = bullet.velocity.x / bullet.velocity.length();
+        assert!((heading.x - direction).abs() < 1e-9);
+
+        // A ship level with the fleet or above it still gets shots dropped straight down
+        fleet.fire(100.0, Position::new(target.x(), 0.0), &mut Rng::new(1));
+        let dropped = fleet.bullets.iter().nth(1).unwrap();
+        assert_eq!(
+            (dropped.velocity.x, dropped.velocity.y),
+            (0.0, fleet.bullet_rate)
+        );
+    }
+
+    #[test]
+    fn lowest_in_column_skips_destroyed_members() {
+        let mut fleet = FleetBuilder::default().build();
+        assert_eq!(fleet.lowest_in_column(0), Some(3));
+        fleet.members[3][0] = None;
+        assert_eq!(fleet.lowest_in_column(0), Some(2));
+        // Gaps above the lowest member don't matter
+        fleet.members[1][0] = None;
+        assert_eq!(fleet.lowest_in_column(0), Some(2));
+        fleet.members[2][0] = None;
+        fleet.members[0][0] = None;
+        assert_eq!(fleet.lowest_in_column(0), None);
+        // Other columns, and ones past the edge of the grid
+        assert_eq!(fleet.lowest_in_column(1), Some(3));
+        assert_eq!(fleet.lowest_in_column(6), None);
+    }
+
+    /// A `width` by `height` sprite of opaque white
+    fn white(width: u32, height: u32) -> Entity {
+        Entity::new(width, height, vec![255; (width * height * 4) as usize]).unwrap()
+    }
+
+    #[test]
+    fn tint_region_is_clipped_to_the_sprite() {
+        let mut entity = white(4, 3);
+        entity.tint_region(3, 2, 5, 5, (0, 128, 255));
+        for (i, pixel) in entity.data.chunks(4).enumerate() {
+            let expected: &[u8] = if i == 11 {
+                &[0, 128, 255, 255]
+            } else {
+                &[255; 4]
+            };
+            assert_eq!(pixel, expected, "pixel {}", i);
+        }
+
+        let mut entity = white(4, 3);
+        entity.tint_region(4, 3, 1, 1, (0, 0, 0));
+        entity.tint_region(0, 0, 0, 3, (0, 0, 0));
+        assert!(entity.data.iter().all(|&channel| channel == 255));
+    }
+
+    #[test]
+    fn tint_keeps_alpha() {
+        let pixels = vec![
+            255, 255, 255, 0, 255, 255, 255, 128, 200, 100, 50, 255, 0, 0, 0, 7,
+        ];
+        let mut entity = Entity::new(4, 1, pixels).unwrap();
+        entity.tint(255, 128, 0);
+        assert_eq!(
+            &entity.data[..],
+            [255, 128, 0, 0, 255, 128, 0, 128, 200, 50, 0, 255, 0, 0, 0, 7]
+        );
+    }
+
+    #[test]
+    fn changing_pixels_drops_the_cached_image() {
+        let mut entity = Entity::from_png(icons::UFO).unwrap();
+        // A stand-in for the ImageData made on drawing, as there's no browser to make a real one
+        entity.image = Some(JsValue::NULL.unchecked_into());
+        let revision = entity.revision;
+
+        entity.data_mut()[0] = 1;
+        assert!(entity.image.is_none());
+        assert_eq!(entity.revision, revision + 1);
+        // The change went to a copy of its own, not the icon every other UFO shares
+        assert!(!Rc::ptr_eq(&entity.data, &icons::shared(icons::UFO).pixels));
+        assert_eq!(entity.data[0], 1);
+    }
+
+    #[test]
+    fn ship_hit_loses_a_life_and_flashes_in_game_time() {
+        let mut ship = ship();
+        ship.update(&steer(100.0, &RIGHT));
+        let mut bullets = BulletPool::new();
+        bullets.fire(ship.inner.position(), Vec2::new(0.0, 0.2));
+
+        assert!(ship.take_hits(&mut bullets, 1000.0));
+        assert_eq!(ship.lives, Ship::DEFAULT_LIVES - 1);
+        assert_eq!(bullets.len(), 0);
+        assert_eq!(ship.inner.flash_until, Some(1000.0 + Entity::FLASH_MS));
+        assert_eq!(ship.inner.position().x(), Ship::center_x(0.0, 600.0, 48.0));
+    }
+
+    #[test]
+    fn barriers_stand_on_their_centers_or_spread_out() {
+        let middle = |barrier: &Barrier| {
+            let (origin, extent) = (barrier.inner.position(), barrier.inner.extent());
+            ((origin.x() + extent.x()) / 2.0, extent.y())
+        };
+        let mut barriers = vec![Barrier::new(), Barrier::new()];
+        Barrier::arrange(&mut barriers, &[], 0.0, 400.0, 300.0);
+        assert_eq!(
+            barriers.iter().map(middle).collect::<Vec<_>>(),
+            [(100.0, 300.0), (300.0, 300.0)]
+        );
+
+        Barrier::arrange(&mut barriers, &[50.0, 220.0], 0.0, 400.0, 300.0);
+        assert_eq!(
+            barriers.iter().map(middle).collect::<Vec<_>>(),
+            [(50.0, 300.0), (220.0, 300.0)]
+        );
     }
 }
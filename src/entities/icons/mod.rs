@@ -1,16 +1,128 @@
-pub const SHIP_WIDTH: u32 = 48;
-pub const SHIP_HEIGHT: u32 = 48;
-pub const SHIP: &[u8; 9216] = include_bytes!("windows_pointer.rgba");
-
-pub const BULLET_WIDTH: u32 = 16;
-pub const BULLET_HEIGHT: u32 = 16;
-pub const BULLET: &[u8; 1024] = include_bytes!("top_side.rgba");
-
-pub const ENEMIES: [&[u8; 1024]; 4] = [ENEMY_LASSO, ENEMY_HOURGLASS, ENEMY_VERTIBEAM, ENEMY_NODROP];
-
-pub const ENEMY_WIDTH: u32 = 16;
-pub const ENEMY_HEIGHT: u32 = 16;
-pub const ENEMY_LASSO: &[u8; 1024] = include_bytes!("pirate.rgba");
-pub const ENEMY_HOURGLASS: &[u8; 1024] = include_bytes!("wait-01.rgba");
-pub const ENEMY_VERTIBEAM: &[u8; 1024] = include_bytes!("vertical-text.rgba");
-pub const ENEMY_NODROP: &[u8; 1024] = include_bytes!("dnd-no-drop.rgba");
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::png;
+
+pub const SHIP: &[u8] = include_bytes!("windows_pointer.png");
+
+pub const BULLET: &[u8] = include_bytes!("top_side.png");
+
+pub const ENEMIES: [&[u8]; 4] = [ENEMY_LASSO, ENEMY_HOURGLASS, ENEMY_VERTIBEAM, ENEMY_NODROP];
+
+pub const ENEMY_LASSO: &[u8] = include_bytes!("pirate.png");
+pub const ENEMY_HOURGLASS: &[u8] = include_bytes!("wait-01.png");
+pub const ENEMY_VERTIBEAM: &[u8] = include_bytes!("vertical-text.png");
+pub const ENEMY_NODROP: &[u8] = include_bytes!("dnd-no-drop.png");
+
+pub const UFO: &[u8] = include_bytes!("ufo.png");
+
+pub const POWER_UP: &[u8] = include_bytes!("power-up.png");
+
+/// An icon decoded to RGBA pixels, sized as the PNG it came from
+#[derive(Clone)]
+pub struct Icon {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Rc<[u8]>,
+}
+
+thread_local! {
+    static SHARED: RefCell<HashMap<&'static [u8], Icon>> = RefCell::new(HashMap::new());
+    static MIRRORED: RefCell<HashMap<&'static [u8], Icon>> = RefCell::new(HashMap::new());
+}
+
+/// One decoded copy of the PNG `icon`, shared by every entity made from it until they change
+/// their own. Only the bundled icons above are decoded this way, and the tests check that each
+/// of those is a valid PNG, so one that isn't panics.
+pub fn shared(icon: &'static [u8]) -> Icon {
+    SHARED.with(|shared| {
+        let mut shared = shared.borrow_mut();
+        let icon = shared.entry(icon).or_insert_with(|| {
+            let image = png::decode(icon).expect("bundled icons are valid PNGs");
+            Icon {
+                width: image.width,
+                height: image.height,
+                pixels: image.data.into(),
+            }
+        });
+        icon.clone()
+    })
+}
+
+/// The PNG `icon` flipped left to right, shared the same way as [`shared`]
+pub fn mirrored(icon: &'static [u8]) -> Icon {
+    MIRRORED.with(|mirrored| {
+        let mut mirrored = mirrored.borrow_mut();
+        let icon = mirrored.entry(icon).or_insert_with(|| {
+            let Icon {
+                width,
+                height,
+                pixels,
+            } = shared(icon);
+            let mut pixels = pixels.to_vec();
+            mirror(&mut pixels, width as usize);
+            Icon {
+                width,
+                height,
+                pixels: pixels.into(),
+            }
+        });
+        icon.clone()
+    })
+}
+
+/// Flip the rows of RGBA `pixels`, each `width` pixels long, left to right
+pub fn mirror(pixels: &mut [u8], width: usize) {
+    if width == 0 {
+        return;
+    }
+    for row in pixels.chunks_exact_mut(width * 4) {
+        for column in 0..(width / 2) {
+            let mirror = width - 1 - column;
+            for channel in 0..4 {
+                row.swap((column * 4) + channel, (mirror * 4) + channel);
+            }
+        }
+    }
+}
+
+/// The `(width, height)` of the PNG `icon`, see [`shared`]
+pub fn size(icon: &'static [u8]) -> (u32, u32) {
+    let icon = shared(icon);
+    (icon.width, icon.height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_icons_decode_to_their_sizes() {
+        assert_eq!(size(SHIP), (48, 48));
+        assert_eq!(size(BULLET), (16, 16));
+        for enemy in ENEMIES {
+            assert_eq!(size(enemy), (16, 16));
+        }
+        assert_eq!(size(UFO), (32, 16));
+        assert_eq!(size(POWER_UP), (16, 16));
+    }
+
+    #[test]
+    fn mirrored_icons_are_flipped_left_to_right() {
+        let (icon, mirrored) = (shared(UFO), mirrored(UFO));
+        assert_eq!((mirrored.width, mirrored.height), (icon.width, icon.height));
+        let width = icon.width as usize * 4;
+        for (row, mirrored_row) in icon.pixels.chunks(width).zip(mirrored.pixels.chunks(width)) {
+            let flipped: Vec<u8> = row.chunks(4).rev().flatten().copied().collect();
+            assert_eq!(mirrored_row, &flipped[..]);
+        }
+    }
+
+    #[test]
+    fn icons_are_decoded_once() {
+        assert!(Rc::ptr_eq(&shared(UFO).pixels, &shared(UFO).pixels));
+        assert!(!Rc::ptr_eq(&shared(UFO).pixels, &shared(SHIP).pixels));
+        assert_eq!(shared(SHIP).pixels.len(), 48 * 48 * 4);
+    }
+}
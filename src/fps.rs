@@ -0,0 +1,74 @@
+//! An on-screen frame rate readout, for keeping an eye on performance while playing
+
+use web_sys::CanvasRenderingContext2d;
+
+use crate::geom::{Position, XY};
+use crate::graphics::{Draw, TimeStamp};
+
+/// Average frame time and rate over the last [`FpsMeter::WINDOW`] frames
+///
+/// The frame times are kept in a fixed ring buffer, so recording one never allocates.
+pub(crate) struct FpsMeter {
+    /// Recent frame times, oldest overwritten first
+    frame_times: [TimeStamp; Self::WINDOW],
+    /// Where the next frame time goes in `frame_times`
+    next: usize,
+    /// How many of `frame_times` have been filled in, until the buffer first wraps around
+    count: usize,
+    /// Where the text's left end sits on its baseline
+    pub(crate) position: Position,
+}
+
+impl FpsMeter {
+    const WINDOW: usize = 30;
+    const FONT: &'static str = "12px monospace";
+    const COLOR: &'static str = "yellow";
+
+    pub(crate) fn new(position: Position) -> Self {
+        Self {
+            frame_times: [0.0; Self::WINDOW],
+            next: 0,
+            count: 0,
+            position,
+        }
+    }
+
+    /// Count a frame that came `elapsed` milliseconds after the last. Times that can't be real,
+    /// zero or less from a clock hiccup, or not numbers at all, are left out.
+    pub(crate) fn record(&mut self, elapsed: TimeStamp) {
+        if !(elapsed > 0.0 && elapsed.is_finite()) {
+            return;
+        }
+        self.frame_times[self.next] = elapsed;
+        self.next = (self.next + 1) % Self::WINDOW;
+        self.count = (self.count + 1).min(Self::WINDOW);
+    }
+
+    /// Average milliseconds per frame, once there's been a frame to measure
+    pub(crate) fn frame_ms(&self) -> Option<TimeStamp> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.frame_times[..self.count].iter().sum::<TimeStamp>() / self.count as TimeStamp)
+    }
+
+    /// Average frames per second, once there's been a frame to measure
+    pub(crate) fn fps(&self) -> Option<f64> {
+        self.frame_ms().map(|ms| 1000.0 / ms)
+    }
+}
+
+impl Draw for FpsMeter {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        let text = match (self.fps(), self.frame_ms()) {
+            (Some(fps), Some(ms)) => format!("FPS: {:.0} ({:.1}ms)", fps, ms),
+            _ => "FPS: --".to_string(),
+        };
+        context.set_font(Self::FONT);
+        context.set_fill_style_str(Self::COLOR);
+        context.set_text_align("left");
+        context
+            .fill_text(&text, self.position.x(), self.position.y())
+            .expect("fill_text");
+    }
+}
@@ -0,0 +1,2065 @@
+//! A single game: its entities, options, and the per-frame update, independent of where its
+//! input and frames come from
+
+use std::collections::VecDeque;
+
+use wasm_bindgen::JsCast;
+use web_sys::{console, CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::audio::{Audio, Sound};
+use crate::difficulty::{Difficulty, DifficultySettings};
+use crate::entities::{
+    Barrier, Boss, BossStats, Explosion, Fleet, FleetBuilder, PowerUp, Ship, Trail, Ufo,
+    ENEMY_SPRITES,
+};
+use crate::fire::{FireControl, FirePattern};
+use crate::fps::FpsMeter;
+use crate::geom::{Distance, Position, Rect, Size, XY};
+use crate::graphics::{
+    self, Backbuffer, Draw, DrawnRect, GameObject, Starfield, TimeStamp, UpdateCtx,
+};
+use crate::highscore::HighScore;
+use crate::keys::KeyBindings;
+use crate::level::{self, Campaign, LevelDef, SpawnStyle};
+use crate::replay::{Replay, ReplayEvent, Snapshot};
+use crate::rng::Rng;
+use crate::saved::Saved;
+use crate::score::Score;
+
+const MARGIN_X: Distance = 30.0;
+const MARGIN_Y: Distance = 48.0;
+/// Default height of the band the ship can roam in free-move mode
+const FREE_MOVE_RANGE: Distance = 120.0;
+const HUD_FONT: &str = "16px monospace";
+const HUD_COLOR: &str = "gray";
+/// Height of the band across the top that the HUD's text is drawn in
+const HUD_HEIGHT: Distance = 30.0;
+/// Default length of the countdown to continue after losing the last life
+const CONTINUE_SECONDS: f64 = 10.0;
+/// Default acceleration for `data-glide`
+const GLIDE_ACCELERATION: f64 = 0.2;
+/// How many frames the game freezes for when the ship loses a life
+const HIT_STOP_FRAMES: u32 = 6;
+/// Default number of barriers for `data-barriers`
+const BARRIERS: usize = 4;
+/// Space between the bottom of the barriers and the top of the ship
+const BARRIER_GAP: Distance = 16.0;
+/// Length of one simulation step, in milliseconds of game time, for 60 steps a second
+const STEP_MS: TimeStamp = 1000.0 / 60.0;
+/// Most steps simulated in one frame, see [`Game::tick`]
+const MAX_STEPS: u32 = 5;
+/// Longest time a single frame is taken to have lasted, in milliseconds
+const MAX_FRAME_MS: TimeStamp = 100.0;
+/// Default seconds between UFO flights for `data-ufo`
+const UFO_INTERVAL: f64 = 20.0;
+/// Height the UFO flies at, between the score and the top of the fleet
+const UFO_Y: Distance = 28.0;
+/// How much faster the fleet moves and fires with each level, as a share of its first-level pace
+const LEVEL_SPEEDUP: f64 = 0.15;
+/// How much lower the fleet starts with each level, up to `MAX_LEVEL_DROP`
+const LEVEL_DROP: Distance = 8.0;
+const MAX_LEVEL_DROP: Distance = 48.0;
+/// Default number of levels between boss fights for `data-boss`
+const BOSS_EVERY: u32 = 3;
+
+/// Freezes the whole game for a few frames on impactful events so they land with more weight
+#[derive(Default)]
+struct HitStop {
+    frames: u32,
+    cooldown: TimeStamp,
+}
+
+impl HitStop {
+    /// Minimum time from the start of one freeze to the next, so a flurry of events can't leave
+    /// the game constantly stuttering
+    const MIN_INTERVAL: TimeStamp = 750.0;
+
+    /// Freeze for the next `frames` frames, unless a freeze happened too recently
+    fn trigger(&mut self, frames: u32) {
+        if self.cooldown <= 0.0 {
+            self.frames = frames;
+            self.cooldown = Self::MIN_INTERVAL;
+        }
+    }
+
+    /// Whether there are frozen frames still to come
+    fn active(&self) -> bool {
+        self.frames > 0
+    }
+
+    /// Advance by one frame that took `elapsed` real time, returning whether that frame is frozen
+    fn tick(&mut self, elapsed: TimeStamp) -> bool {
+        self.cooldown -= elapsed;
+        if self.frames > 0 {
+            self.frames -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Where a game is in its lifecycle
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GameState {
+    Playing,
+    /// Out of lives, with `seconds_left` to press fire and carry on from the current level
+    Continue {
+        seconds_left: f64,
+    },
+    GameOver,
+    /// The whole fleet has been destroyed
+    Victory,
+}
+
+impl GameState {
+    /// The state to move to on losing the last life: a countdown of `continue_seconds` (or
+    /// [`CONTINUE_SECONDS`] if that's not a usable length) if continuing is allowed at all, or
+    /// straight to game over if it's `None`
+    fn out_of_lives(continue_seconds: Option<f64>) -> Self {
+        match continue_seconds {
+            Some(seconds) => GameState::Continue {
+                seconds_left: if seconds > 0.0 {
+                    seconds
+                } else {
+                    CONTINUE_SECONDS
+                },
+            },
+            None => GameState::GameOver,
+        }
+    }
+}
+
+/// Something that happened during an [`update`](Game::update), for the parts of the game that
+/// reach out to the browser (sound and saved scores) to act on afterwards
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The ship fired
+    Shot,
+    /// A member of the fleet, the UFO, or the boss was hit
+    Hit,
+    /// The ship lost a life
+    ShipDestroyed,
+    /// The ship caught a power-up
+    PowerUp,
+    /// The game ended, won or lost, with this final score
+    Finished(u32),
+}
+
+/// A player action, from the keyboard, mouse, or touch screen, or sent in directly with
+/// [`Game::send_input`]
+///
+/// Steering inputs carry whether the control is now held (`true`) or let go (`false`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameInput {
+    Left(bool),
+    Right(bool),
+    /// Steer up, in free-move mode
+    Up(bool),
+    /// Steer down, in free-move mode
+    Down(bool),
+    Fire,
+    ToggleFiringLine,
+    /// Start a new game, once the current one is over or won
+    Restart,
+    /// Pause the game, or resume it if it's paused
+    TogglePause,
+    /// Put the middle of the ship at this x position, in game coordinates, as far as its range
+    /// allows; see [`Game::game_x`]
+    MoveTo(Distance),
+    /// Steer toward this x position, in game coordinates, at the ship's usual speed, or go back
+    /// to steering by the other controls once it's `None`
+    SteerTo(Option<Distance>),
+    /// Show or hide the frame rate readout
+    ToggleFps,
+}
+
+/// Which steering controls are held down right now, built up from [`GameInput`]s as they arrive
+///
+/// Opposite controls held together cancel out: holding left and right at once stops the ship,
+/// rather than whichever was pressed last winning, and letting go of either carries on in the
+/// direction of the other. Firing, pausing, and the other one-off actions aren't held, so they
+/// aren't part of the snapshot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Input {
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+}
+
+impl Input {
+    /// Take in `input`, returning whether it was a steering control
+    pub fn apply(&mut self, input: GameInput) -> bool {
+        match input {
+            GameInput::Left(held) => self.left = held,
+            GameInput::Right(held) => self.right = held,
+            GameInput::Up(held) => self.up = held,
+            GameInput::Down(held) => self.down = held,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Which way to steer along x: `-1.0` for left, `1.0` for right, or `0.0` for neither (or
+    /// both)
+    pub fn x(&self) -> Distance {
+        axis(self.left, self.right)
+    }
+
+    /// Which way to steer along y, with `1.0` down the screen, see [`x`](Self::x)
+    pub fn y(&self) -> Distance {
+        axis(self.up, self.down)
+    }
+}
+
+fn axis(negative: bool, positive: bool) -> Distance {
+    Distance::from(u8::from(positive)) - Distance::from(u8::from(negative))
+}
+
+/// Everything a level's fleet is built from, read from the canvas once so that moving on to a
+/// new level doesn't have to go back to the page
+struct FleetSetup {
+    /// Level definitions from `data-campaign` or `data-level`, in place of the default formation
+    levels: Vec<LevelDef>,
+    difficulty: DifficultySettings,
+    /// Whether each row of the fleet is colored differently, with `data-tint-rows`
+    tint_rows: bool,
+    /// Whether bullets only hit members where they're visible, with `data-pixel-collisions`
+    pixel_collisions: bool,
+    /// How members animate into place, from `data-spawn`, overriding the level's choice
+    spawn_style: Option<SpawnStyle>,
+    /// Whether members hover up and down as the fleet moves, with `data-bob`
+    bob: bool,
+    /// Where the fleet's top row starts out on the first level, from `data-fleet-top`
+    top: Distance,
+    /// How many levels apart boss fights come, with `data-boss`
+    boss_every: Option<u32>,
+}
+
+impl FleetSetup {
+    /// The fleet for level `number`, counting from 1, in a play area `width` across
+    fn build(&self, width: Distance, number: u32) -> Fleet {
+        let difficulty = &self.difficulty;
+        let level = self.level(number);
+        // Each level after the first, the fleet moves and fires faster and starts out lower
+        let levels_in = f64::from(number.saturating_sub(1));
+        let speedup = Self::speedup(number);
+        let mut builder = FleetBuilder::default()
+            .bounds(MARGIN_X, width - MARGIN_X)
+            .spacing(MARGIN_Y)
+            .top(self.top + (LEVEL_DROP * levels_in).min(MAX_LEVEL_DROP))
+            .rate(difficulty.fleet_rate);
+        if let Some(level) = level {
+            builder = builder.level(level);
+        }
+        let mut enemies = builder.build();
+        enemies.horizontal_rate *= speedup;
+        if self.tint_rows {
+            enemies = enemies.with_row_tints(&Fleet::ROW_TINTS);
+        }
+        if self.bob {
+            enemies = enemies.with_bob();
+        }
+        if level.is_some_and(|level| level.start_left) {
+            enemies = enemies.with_direction(-1.0);
+        }
+        // Levels can script the fleet's fire; otherwise it fires from random columns
+        let fire_pattern = match level {
+            Some(level) => level.fire_pattern.clone().unwrap_or(FirePattern::Random {
+                interval: level
+                    .enemy_fire_interval
+                    .unwrap_or(difficulty.enemy_fire_interval)
+                    / speedup,
+            }),
+            None => FirePattern::Random {
+                interval: difficulty.enemy_fire_interval / speedup,
+            },
+        };
+        enemies.fire = FireControl::new(fire_pattern);
+        enemies.bullet_rate = difficulty.enemy_bullet_rate;
+        enemies.pixel_collisions = self.pixel_collisions;
+        if let Some(level) = level {
+            enemies.spawn_style = level.spawn.unwrap_or(enemies.spawn_style);
+            enemies.spawn_stagger = level.spawn_stagger.unwrap_or(enemies.spawn_stagger);
+        }
+        enemies.spawn_style = self.spawn_style.unwrap_or(enemies.spawn_style);
+        enemies.start_spawn();
+        enemies
+    }
+
+    /// The boss to fight once level `number`'s fleet is cleared, if it has one: every level
+    /// given one by its definition, and every `data-boss`th level otherwise
+    fn boss(&self, number: u32) -> Option<BossStats> {
+        let def = match self.level(number).and_then(|level| level.boss) {
+            Some(def) => def,
+            None if self
+                .boss_every
+                .is_some_and(|every| number.is_multiple_of(every)) =>
+            {
+                Default::default()
+            }
+            None => return None,
+        };
+        let defaults = BossStats::default();
+        // Bosses speed up from level to level along with the fleet
+        let speedup = Self::speedup(number);
+        Some(BossStats {
+            health: def.health.unwrap_or(defaults.health),
+            scale: def.scale.unwrap_or(defaults.scale),
+            rate: def.rate.unwrap_or(defaults.rate) * speedup,
+            fire_interval: def.fire_interval.unwrap_or(defaults.fire_interval) / speedup,
+            points: def.points.unwrap_or(defaults.points),
+        })
+    }
+
+    /// The definition for level `number`, counting from 1, if there are any: a campaign's levels
+    /// in turn, with the last one played over for any levels after it
+    fn level(&self, number: u32) -> Option<&LevelDef> {
+        let index = (number as usize).saturating_sub(1);
+        self.levels.get(index).or(self.levels.last())
+    }
+
+    /// Where level `number`'s definition puts its bunkers, if it does
+    fn bunkers(&self, number: u32) -> &[Distance] {
+        self.level(number).map_or(&[], |level| &level.bunkers)
+    }
+
+    /// How much faster than on the first level things move and fire on level `number`
+    fn speedup(number: u32) -> f64 {
+        1.0 + (LEVEL_SPEEDUP * f64::from(number.saturating_sub(1)))
+    }
+}
+
+/// How a game is set up: read from its canvas's `data-*` attributes by
+/// [`from_canvas`](Self::from_canvas), or filled in directly for a game with no canvas, see
+/// [`Game::with_config`]. The default is a canvas with none of the attributes set.
+#[derive(Clone, Debug)]
+pub struct GameConfig {
+    /// Whole-number multiple of its size the game is drawn at
+    pub zoom: u32,
+    /// Whether to zoom further by the display's pixel ratio
+    pub hidpi: bool,
+    pub difficulty: Difficulty,
+    pub subpixel: bool,
+    pub high_contrast: bool,
+    pub outline: bool,
+    /// Seed for all of the game's randomness
+    pub seed: u64,
+    /// A level definition to play in place of the default formation
+    pub level: Option<LevelDef>,
+    /// Levels to play in order, in place of `level`
+    pub campaign: Option<Campaign>,
+    /// A CSS color to paint behind everything
+    pub background: Option<String>,
+    pub pixel_collisions: bool,
+    pub tint_rows: bool,
+    pub spawn_style: Option<SpawnStyle>,
+    pub bob: bool,
+    /// Where the fleet's top row starts out on the first level
+    pub fleet_top: Distance,
+    /// How many levels apart boss fights come, if there are any
+    pub boss_every: Option<u32>,
+    /// Height of the band the ship can roam, in free-move mode
+    pub free_move_range: Option<Distance>,
+    /// Least time in milliseconds between shots, in place of the ship's usual cooldown
+    pub fire_cooldown: Option<TimeStamp>,
+    pub wrap: bool,
+    /// Acceleration for a gliding ship, above 0 and up to 1
+    pub glide: Option<f64>,
+    /// Number of fading afterimages behind each of the ship's bullets, zero for none
+    pub bullet_trail: usize,
+    /// Opacity of each afterimage relative to the one in front
+    pub bullet_trail_fade: f64,
+    /// Number of barriers above the ship's row
+    pub barriers: usize,
+    /// Milliseconds between UFO flights, if there are any
+    pub ufo_interval: Option<TimeStamp>,
+    /// Number of background stars, if there's a starfield
+    pub stars: Option<usize>,
+    pub full_redraw: bool,
+    /// Number of levels to clear to win, or `None` to play on until the ship is destroyed
+    pub levels: Option<u32>,
+    pub power_ups: bool,
+    pub sound: bool,
+    /// Length of the countdown to continue after losing the last life, if continuing is allowed
+    pub continue_seconds: Option<f64>,
+    pub continue_resets_score: bool,
+    pub keys: KeyBindings,
+    pub show_time: bool,
+    pub show_cooldown: bool,
+    pub firing_line: bool,
+    pub fps: bool,
+    pub auto_resume: bool,
+    /// A recorded game to play back, in place of `seed` and the player's inputs until it ends
+    pub replay: Option<Replay>,
+    /// A paused game to pick back up from
+    pub resume: Option<Snapshot>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            zoom: 1,
+            hidpi: false,
+            difficulty: Difficulty::default(),
+            subpixel: false,
+            high_contrast: false,
+            outline: false,
+            seed: 0,
+            level: None,
+            campaign: None,
+            background: None,
+            pixel_collisions: false,
+            tint_rows: false,
+            spawn_style: None,
+            bob: false,
+            fleet_top: FleetBuilder::TOP,
+            boss_every: None,
+            free_move_range: None,
+            fire_cooldown: None,
+            wrap: false,
+            glide: None,
+            bullet_trail: 0,
+            bullet_trail_fade: Trail::DEFAULT_FADE,
+            barriers: 0,
+            ufo_interval: None,
+            stars: None,
+            full_redraw: false,
+            levels: None,
+            power_ups: false,
+            sound: false,
+            continue_seconds: None,
+            continue_resets_score: false,
+            keys: KeyBindings::default(),
+            show_time: false,
+            show_cooldown: false,
+            firing_line: false,
+            fps: false,
+            auto_resume: true,
+            replay: None,
+            resume: None,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Read the setup from `canvas`'s `data-*` attributes, logging any that can't be used
+    pub fn from_canvas(canvas: &HtmlCanvasElement) -> Self {
+        let flag = |name| canvas.has_attribute(name);
+        let level =
+            canvas
+                .get_attribute("data-level")
+                .and_then(|json| match level::load_level(&json) {
+                    Ok(level) => Some(level),
+                    Err(err) => {
+                        console::error_1(&format!("Ignoring data-level, {}", err).into());
+                        None
+                    }
+                });
+        let campaign = canvas.get_attribute("data-campaign").and_then(|json| {
+            match level::load_campaign(&json) {
+                Ok(campaign) => Some(campaign).filter(|campaign| !campaign.is_empty()),
+                Err(err) => {
+                    console::error_1(&format!("Ignoring data-campaign, {}", err).into());
+                    None
+                }
+            }
+        });
+        Self {
+            // `data-zoom` draws the game at a whole-number multiple of its size
+            zoom: canvas
+                .get_attribute("data-zoom")
+                .and_then(|zoom| zoom.parse().ok())
+                .filter(|&zoom| zoom >= 1)
+                .unwrap_or(1),
+            // `data-hidpi` zooms further by the display's pixel ratio, for a canvas whose backing
+            // store has been sized to match (see `start_game`)
+            hidpi: flag("data-hidpi"),
+            difficulty: match canvas.get_attribute("data-difficulty") {
+                Some(name) => name.parse().unwrap_or_else(|err| {
+                    console::log_1(&format!("Using normal difficulty, {}", err).into());
+                    Difficulty::Normal
+                }),
+                None => Difficulty::default(),
+            }
+            .customize(|name| canvas.get_attribute(name)),
+            subpixel: flag("data-subpixel"),
+            // Accessibility options: `data-high-contrast` recolors the sprites, and `data-outline`
+            // traces a box around each one
+            high_contrast: flag("data-high-contrast"),
+            outline: flag("data-outline"),
+            // A fixed `data-seed` makes the randomness in a game repeatable
+            seed: canvas
+                .get_attribute("data-seed")
+                .and_then(|seed| seed.parse().ok())
+                .unwrap_or_else(|| js_sys::Date::now() as u64),
+            // A CSS color in `data-background` is painted behind everything each frame;
+            // otherwise the canvas is cleared to transparent and the page shows through
+            background: canvas.get_attribute("data-background").or_else(|| {
+                let first = campaign.as_ref().and_then(|campaign| campaign.first());
+                first.or(level.as_ref())?.background.clone()
+            }),
+            // A level definition in `data-level` replaces the default fleet, and a JSON array of
+            // them in `data-campaign` is played through in order
+            level,
+            campaign,
+            // `data-pixel-collisions` only counts hits where both sprites are visible, rather
+            // than wherever their bounding boxes overlap
+            pixel_collisions: flag("data-pixel-collisions"),
+            // `data-tint-rows` colors each row of the fleet differently
+            tint_rows: flag("data-tint-rows"),
+            // Members can animate into place before play starts, chosen with `data-spawn`
+            // (`fade` or `drop-in`) or by the level
+            spawn_style: canvas.get_attribute("data-spawn").map(|style| {
+                style.parse().unwrap_or_else(|err| {
+                    console::log_1(&format!("Not animating the fleet in, {}", err).into());
+                    SpawnStyle::None
+                })
+            }),
+            // `data-bob` has the fleet's members hover gently up and down
+            bob: flag("data-bob"),
+            // `data-fleet-top` starts the fleet higher or lower, for taller or shorter canvases
+            fleet_top: canvas
+                .get_attribute("data-fleet-top")
+                .and_then(|top| top.parse().ok())
+                .filter(|top: &Distance| top.is_finite())
+                .unwrap_or(FleetBuilder::TOP),
+            // `data-boss` ends every third level (or every given number) with a boss fight
+            boss_every: canvas.get_attribute("data-boss").map(|every| {
+                every
+                    .parse()
+                    .ok()
+                    .filter(|&every| every >= 1)
+                    .unwrap_or(BOSS_EVERY)
+            }),
+            // Free-move mode (`data-free-move`, optionally giving the height of the band in
+            // pixels) lets the ship move up and down as well as side to side
+            free_move_range: canvas
+                .get_attribute("data-free-move")
+                .map(|range| range.parse().unwrap_or(FREE_MOVE_RANGE)),
+            // `data-fire-cooldown` sets the least time in milliseconds between shots, zero for
+            // none
+            fire_cooldown: canvas
+                .get_attribute("data-fire-cooldown")
+                .and_then(|cooldown| cooldown.parse().ok())
+                .filter(|&cooldown: &TimeStamp| cooldown >= 0.0),
+            // `data-wrap` lets the ship leave one side of the screen and come back in on the
+            // other
+            wrap: flag("data-wrap"),
+            // `data-glide` eases the ship in and out of moving, with an optional acceleration
+            // above 0 and up to 1 (lower is floatier)
+            glide: canvas.get_attribute("data-glide").map(|glide| {
+                glide
+                    .parse()
+                    .ok()
+                    .filter(|acceleration| *acceleration > 0.0 && *acceleration <= 1.0)
+                    .unwrap_or(GLIDE_ACCELERATION)
+            }),
+            // `data-bullet-trail` gives bullets that many fading afterimages, each copy's opacity
+            // multiplied by `data-bullet-trail-fade` relative to the one in front
+            bullet_trail: canvas
+                .get_attribute("data-bullet-trail")
+                .and_then(|length| length.parse().ok())
+                .unwrap_or(0),
+            bullet_trail_fade: canvas
+                .get_attribute("data-bullet-trail-fade")
+                .and_then(|fade| fade.parse().ok())
+                .filter(|fade| (0.0..=1.0).contains(fade))
+                .unwrap_or(Trail::DEFAULT_FADE),
+            // `data-barriers` puts bunkers (`BARRIERS` of them, or the given number) just above
+            // the ship's row
+            barriers: canvas
+                .get_attribute("data-barriers")
+                .map_or(0, |count| count.parse().unwrap_or(BARRIERS)),
+            // `data-ufo` sends a UFO across the top every `UFO_INTERVAL` seconds, or the given
+            // number
+            ufo_interval: canvas.get_attribute("data-ufo").map(|seconds| {
+                let seconds = seconds.parse().ok().filter(|&seconds: &f64| seconds > 0.0);
+                seconds.unwrap_or(UFO_INTERVAL) * 1000.0
+            }),
+            // Background stars are opt-in, with the number of stars given by `data-stars`
+            stars: canvas
+                .get_attribute("data-stars")
+                .and_then(|count| count.parse().ok()),
+            // Only the parts of the screen that change are redrawn, unless `data-full-redraw`
+            // asks for every frame to be drawn from scratch
+            full_redraw: flag("data-full-redraw"),
+            // Clearing the fleet moves on to a harder level, until the `data-levels`th is won
+            levels: canvas
+                .get_attribute("data-levels")
+                .and_then(|levels| levels.parse().ok())
+                .filter(|&levels| levels >= 1),
+            // `data-power-ups` has some of the fleet drop power-ups for the ship to catch
+            power_ups: flag("data-power-ups"),
+            // Sound effects are opt-in with `data-sound`
+            sound: flag("data-sound"),
+            // `data-continue` offers a countdown to carry on after losing the last life, lasting
+            // the given number of seconds or `CONTINUE_SECONDS` if no usable length is given
+            continue_seconds: canvas
+                .get_attribute("data-continue")
+                .map(|seconds| seconds.parse().unwrap_or(0.0)),
+            // Continuing keeps the score unless `data-continue-reset-score` is set
+            continue_resets_score: flag("data-continue-reset-score"),
+            // `data-keys` picks another set of keys to play with (`wasd`, `arrows`, or `zx`)
+            keys: match canvas.get_attribute("data-keys") {
+                Some(name) => name.parse().unwrap_or_else(|err| {
+                    console::log_1(&format!("Using the default keys, {}", err).into());
+                    KeyBindings::default()
+                }),
+                None => KeyBindings::default(),
+            },
+            show_time: flag("data-show-time"),
+            show_cooldown: flag("data-cooldown-bar"),
+            // An aiming aid tracing where the ship's shots will go, on from the start with
+            // `data-firing-line` and toggled with the L key
+            firing_line: flag("data-firing-line"),
+            fps: flag("data-fps"),
+            auto_resume: canvas.get_attribute("data-auto-resume").as_deref() != Some("false"),
+            // `data-replay` plays back a recording, as the base64 that `Replay::to_base64` gives
+            // or, left empty, the last game played to the end on this browser
+            replay: canvas
+                .get_attribute("data-replay")
+                .and_then(|replay| match replay.as_str() {
+                    "" => Saved::load().replay(),
+                    text => Replay::from_base64(text)
+                        .map_err(|err| {
+                            console::error_1(&format!("Ignoring data-replay, {}", err).into())
+                        })
+                        .ok(),
+                }),
+            // `data-resume` picks up where the last game paused on this browser left off
+            resume: flag("data-resume")
+                .then(|| Saved::load().snapshot())
+                .flatten(),
+        }
+    }
+
+    /// The same setup, for starting over once the game it began is over: a replay or a resumed
+    /// game only stands in for the first game
+    fn for_restart(self) -> Self {
+        Self {
+            replay: None,
+            resume: None,
+            ..self
+        }
+    }
+}
+
+/// One game on one canvas
+///
+/// The page drives a game by forwarding key presses as [`GameInput`]s and calling
+/// [`tick`](Self::tick) from `requestAnimationFrame`, but nothing here depends on either: a test
+/// or tutorial can send inputs and step frames with whatever timestamps it likes, and with a fixed
+/// `data-seed` the game plays out the same way every time.
+///
+/// [`tick`](Self::tick) is made of two halves that can also be called on their own:
+/// [`update`](Self::update) moves the simulation on, and [`render`](Self::render) draws it. Only
+/// `render` touches the canvas, and `update` leaves sound and saving scores to its caller through
+/// the [`GameEvent`]s it returns, so a harness can step the game logic with `update` alone. A
+/// game set up with [`with_config`](Self::with_config) has no canvas at all, and runs anywhere,
+/// including in native tests.
+pub struct Game {
+    /// Where the game is drawn, unless it was set up without a canvas
+    surface: Option<Surface>,
+    /// What the game was set up with, for setting it up again on a restart without a canvas
+    config: GameConfig,
+    width: Distance,
+    height: Distance,
+    /// Zoom asked for by the page, before any scaling for the display
+    zoom: u32,
+    hidpi: bool,
+    subpixel: bool,
+    blend: bool,
+    high_contrast: bool,
+    outline: bool,
+    background: Option<String>,
+    starfield: Option<Starfield>,
+    /// Clear and redraw the whole canvas every frame, instead of only the parts that changed
+    full_redraw: bool,
+    /// Whether the next frame has to be drawn in full anyway, because something drawn outside
+    /// of the measured sprites (like a banner) is on screen, or the canvas has been cleared
+    redraw_all: bool,
+    /// Where everything was drawn in the last frame, to find what changed in the next
+    drawn: Vec<DrawnRect>,
+    enemies: Fleet,
+    /// The level being played, counting from 1
+    level: u32,
+    /// Number of levels to clear to win, or `None` to play on until the ship is destroyed
+    levels: Option<u32>,
+    fleet_setup: FleetSetup,
+    /// The boss being fought, once a level with one has had its fleet cleared
+    boss: Option<Boss>,
+    barriers: Vec<Barrier>,
+    ufo: Option<Ufo>,
+    /// Milliseconds between UFO flights, if there are any
+    ufo_interval: Option<TimeStamp>,
+    /// Milliseconds of play left until the next UFO sets off
+    ufo_countdown: TimeStamp,
+    /// Chance of a destroyed member of the fleet dropping a power-up
+    drop_chance: f64,
+    power_ups: Vec<PowerUp>,
+    ship: Ship,
+    explosions: Vec<Explosion>,
+    audio: Audio,
+    /// How far down the fleet can come before it's invaded, the top of the ship's starting row
+    invasion_line: Distance,
+    /// Lives the ship starts with, and gets back on continuing
+    lives: u32,
+    /// Length of the countdown to continue after losing the last life, if continuing is allowed
+    continue_seconds: Option<f64>,
+    /// Whether continuing starts the score over from zero
+    continue_resets_score: bool,
+    score: Score,
+    high_score: HighScore,
+    rng: Rng,
+    free_move: bool,
+    keys: KeyBindings,
+    hit_stop: HitStop,
+    /// Total game time played, shown in the corner with `data-show-time`
+    play_time: TimeStamp,
+    show_time: bool,
+    show_cooldown: bool,
+    show_firing_line: bool,
+    /// Frame rate readout in the bottom corner, on with `data-fps` and toggled with the F key
+    show_fps: bool,
+    fps: FpsMeter,
+    paused: bool,
+    /// Whether the current pause came from the page losing focus, rather than the player, so
+    /// only those pauses are lifted automatically on regaining focus (unless turned off with
+    /// `data-auto-resume="false"`)
+    auto_paused: bool,
+    auto_resume: bool,
+    last_ts: TimeStamp,
+    /// Game time that has passed but is less than a whole step, waiting to be simulated
+    unsimulated: TimeStamp,
+    state: GameState,
+    /// Inputs waiting to be applied at the start of the next frame
+    inputs: VecDeque<GameInput>,
+    /// Steering controls held as of the last input applied
+    input: Input,
+    /// Multiplier applied to the time elapsed between frames before it's simulated, see
+    /// [`set_time_scale`](Self::set_time_scale)
+    time_scale: f64,
+    /// The seed and every input sent so far, see [`send_input`](Self::send_input)
+    replay: Replay,
+    /// Inputs from a replay still to be played back, each once the game time reaches its own
+    playback: VecDeque<ReplayEvent>,
+    /// Where the last finished game's replay and the paused game are kept
+    saved: Saved,
+}
+
+/// The canvas a game is drawn on, and what it's drawn with
+struct Surface {
+    /// Kept so a restart can set the game up again from the canvas's attributes
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    /// Where frames are drawn before going onto the canvas, unless built with `direct-draw`
+    backbuffer: Option<Backbuffer>,
+}
+
+impl Surface {
+    /// The context frames are drawn with, the backbuffer's if there is one
+    fn target(&self) -> &CanvasRenderingContext2d {
+        match &self.backbuffer {
+            Some(backbuffer) => backbuffer.context(),
+            None => &self.context,
+        }
+    }
+
+    /// Copy a frame drawn on the backbuffer onto the canvas
+    fn present(&self) {
+        if let Some(backbuffer) = &self.backbuffer {
+            backbuffer.present(&self.context);
+        }
+    }
+}
+
+impl Game {
+    /// Set up a game on `canvas`, configured by its `data-*` attributes, with its clock starting
+    /// at `ts`. Fails if the canvas is too small to play on.
+    pub fn new(canvas: &HtmlCanvasElement, ts: TimeStamp) -> Result<Self, String> {
+        Self::on_canvas(canvas, GameConfig::from_canvas(canvas), ts)
+    }
+
+    /// Set up a game on `canvas`, configured by `config`, see [`new`](Self::new)
+    fn on_canvas(
+        canvas: &HtmlCanvasElement,
+        config: GameConfig,
+        ts: TimeStamp,
+    ) -> Result<Self, String> {
+        // Everything from here on works in game coordinates, which are the canvas's size divided
+        // by the zoom
+        let scale = if config.hidpi {
+            config.zoom * graphics::device_pixel_zoom()
+        } else {
+            config.zoom
+        };
+        let width = Distance::from(canvas.width() / scale);
+        let height = Distance::from(canvas.height() / scale);
+        let mut game = Self::with_config(width, height, config)
+            .map_err(|err| format!("Not starting a game on #{}, {}", canvas.id(), err))?;
+
+        // A canvas that's already given out another kind of context has no 2d one to give
+        let context = canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+            .ok_or_else(|| {
+                format!(
+                    "Not starting a game on #{}, it has no 2d context",
+                    canvas.id()
+                )
+            })?;
+        let backbuffer = if cfg!(feature = "direct-draw") {
+            None
+        } else {
+            Backbuffer::new(canvas.width(), canvas.height())
+                .map_err(|err| console::log_1(&format!("Drawing directly, {:?}", err).into()))
+                .ok()
+        };
+        game.surface = Some(Surface {
+            canvas: canvas.clone(),
+            context,
+            backbuffer,
+        });
+        game.high_score = HighScore::load();
+        game.saved = Saved::load();
+        game.last_ts = ts;
+        Ok(game)
+    }
+
+    /// Set up a game `width` by `height` in game pixels, configured by `config`, that isn't drawn
+    /// anywhere: it runs just the same, but [`render`](Self::render) does nothing and the best
+    /// score isn't saved. Its clock starts at the first [`tick`](Self::tick). Fails if the play
+    /// area is too small to play in.
+    pub fn with_config(
+        width: Distance,
+        height: Distance,
+        config: GameConfig,
+    ) -> Result<Self, String> {
+        // Nothing fits in an empty play area, and the fleet and ship would be given inverted
+        // bounds
+        if !(width >= 2.0 * MARGIN_X && height >= 2.0 * MARGIN_Y) {
+            return Err(format!("a {}x{} play area is too small", width, height));
+        }
+        let difficulty = config.difficulty.settings();
+        // Sprites are recolored as they're created, so this is set before building any entities
+        graphics::set_high_contrast(config.high_contrast);
+        // A replay only plays back the same way from the seed it was recorded with
+        let seed = config
+            .replay
+            .as_ref()
+            .map_or(config.seed, |replay| replay.seed);
+        let mut rng = Rng::new(seed);
+
+        let fleet_setup = FleetSetup {
+            levels: match &config.campaign {
+                Some(campaign) => campaign.clone(),
+                None => config.level.iter().cloned().collect(),
+            },
+            difficulty,
+            tint_rows: config.tint_rows,
+            pixel_collisions: config.pixel_collisions,
+            spawn_style: config.spawn_style,
+            bob: config.bob,
+            top: config.fleet_top,
+            boss_every: config.boss_every,
+        };
+        let enemies = fleet_setup.build(width, 1);
+        let mut ship = Ship::new(
+            0.5,
+            difficulty.bullet_rate,
+            height - MARGIN_Y,
+            config.free_move_range.unwrap_or(0.0),
+            MARGIN_X,
+            width - MARGIN_X,
+        );
+        ship.max_bullets = difficulty.max_bullets;
+        ship.lives = difficulty.lives;
+        if let Some(cooldown) = config.fire_cooldown {
+            ship.cooldown_ms = cooldown;
+        }
+        ship.set_wrap(config.wrap);
+        ship.pixel_collisions = config.pixel_collisions;
+        // Wrapping takes precedence over gliding
+        if let (Some(acceleration), false) = (config.glide, config.wrap) {
+            ship.set_glide(acceleration);
+        }
+        ship.bullet_trail = (config.bullet_trail > 0).then_some(Trail {
+            length: config.bullet_trail,
+            fade: config.bullet_trail_fade,
+        });
+        // A level that places its own bunkers gets those, in place of `data-barriers`
+        let bunkers = fleet_setup.bunkers(1);
+        let count = if bunkers.is_empty() {
+            config.barriers
+        } else {
+            bunkers.len()
+        };
+        let mut barriers: Vec<Barrier> = (0..count).map(|_| Barrier::new()).collect();
+        let invasion_line = ship.inner.position().y();
+        Barrier::arrange(
+            &mut barriers,
+            bunkers,
+            MARGIN_X,
+            width - MARGIN_X,
+            invasion_line - BARRIER_GAP,
+        );
+        let starfield = config
+            .stars
+            .map(|count| Starfield::new(count, Size::new(width, height), &mut rng));
+        let background = config.background.clone();
+        let blend = background.is_some() || starfield.is_some();
+
+        let playback = config.replay.as_ref().map_or_else(VecDeque::new, |replay| {
+            replay.events.iter().copied().collect()
+        });
+        let resume = config.resume.clone();
+        let mut game = Self {
+            surface: None,
+            width,
+            height,
+            zoom: config.zoom,
+            hidpi: config.hidpi,
+            subpixel: config.subpixel,
+            blend,
+            high_contrast: config.high_contrast,
+            outline: config.outline,
+            background,
+            starfield,
+            full_redraw: config.full_redraw,
+            redraw_all: true,
+            drawn: Vec::new(),
+            enemies,
+            level: 1,
+            // A campaign is won by clearing its last level, unless `data-levels` says otherwise
+            levels: config.levels.or_else(|| {
+                let campaign = config.campaign.as_ref()?;
+                u32::try_from(campaign.len()).ok()
+            }),
+            fleet_setup,
+            boss: None,
+            barriers,
+            ufo: None,
+            ufo_interval: config.ufo_interval,
+            ufo_countdown: config.ufo_interval.unwrap_or(0.0),
+            drop_chance: if config.power_ups {
+                PowerUp::DROP_CHANCE
+            } else {
+                0.0
+            },
+            power_ups: Vec::new(),
+            invasion_line,
+            ship,
+            explosions: Vec::new(),
+            audio: Audio::new(config.sound),
+            lives: difficulty.lives,
+            continue_seconds: config.continue_seconds,
+            continue_resets_score: config.continue_resets_score,
+            score: Score::new(Position::new(MARGIN_X, 24.0)),
+            high_score: HighScore::default(),
+            rng,
+            free_move: config.free_move_range.is_some(),
+            keys: config.keys.clone(),
+            hit_stop: HitStop::default(),
+            play_time: 0.0,
+            show_time: config.show_time,
+            show_cooldown: config.show_cooldown,
+            show_firing_line: config.firing_line,
+            show_fps: config.fps,
+            fps: FpsMeter::new(Position::default()),
+            paused: false,
+            auto_paused: false,
+            auto_resume: config.auto_resume,
+            // Time doesn't start passing until the first frame
+            last_ts: TimeStamp::NAN,
+            unsimulated: 0.0,
+            state: GameState::Playing,
+            inputs: VecDeque::new(),
+            input: Input::default(),
+            replay: Replay::new(seed),
+            playback,
+            saved: Saved::default(),
+            time_scale: 1.0,
+            config,
+        };
+        if let Some(snapshot) = &resume {
+            game.restore(snapshot);
+        }
+        Ok(game)
+    }
+
+    /// The input a key press (or release, if `pressed` is false) stands for with this game's
+    /// key bindings, if any
+    pub fn key_input(&self, key: &str, pressed: bool) -> Option<GameInput> {
+        self.keys.input(key, pressed, self.free_move)
+    }
+
+    /// Play with `keys` from now on, in place of the ones chosen by `data-keys`
+    pub fn set_key_bindings(&mut self, keys: KeyBindings) {
+        self.keys = keys;
+    }
+
+    /// Canvas pixels per game pixel: the page's zoom, times the display's pixel ratio with
+    /// `data-hidpi`. The ratio is looked up each time as it changes when the window moves to
+    /// another screen.
+    fn scale(&self) -> u32 {
+        if self.hidpi {
+            self.zoom * graphics::device_pixel_zoom()
+        } else {
+            self.zoom
+        }
+    }
+
+    /// Pick up a change in the size of the canvas, moving the edges of the play area to match.
+    /// Anything left outside the new area is brought back in, and a canvas too small to play on
+    /// is ignored.
+    pub fn resize(&mut self) {
+        let Some(surface) = &self.surface else {
+            return;
+        };
+        let width = Distance::from(surface.canvas.width() / self.scale());
+        let height = Distance::from(surface.canvas.height() / self.scale());
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        if width < 2.0 * MARGIN_X || height < 2.0 * MARGIN_Y {
+            let message = format!(
+                "Ignoring resize, a {}x{} play area is too small",
+                width, height
+            );
+            console::log_1(&message.into());
+            return;
+        }
+
+        // The ship's row, and the line the fleet can't cross, keep their distance from the bottom
+        let shift_y = height - self.height;
+        self.enemies.set_bounds(MARGIN_X, width - MARGIN_X);
+        if let Some(boss) = self.boss.as_mut() {
+            boss.set_bounds(MARGIN_X, width - MARGIN_X);
+        }
+        self.ship.set_bounds(MARGIN_X, width - MARGIN_X, shift_y);
+        self.invasion_line += shift_y;
+        let barrier_bottom = self.invasion_line - BARRIER_GAP;
+        Barrier::arrange(
+            &mut self.barriers,
+            self.fleet_setup.bunkers(self.level),
+            MARGIN_X,
+            width - MARGIN_X,
+            barrier_bottom,
+        );
+        if let Some(starfield) = self.starfield.as_mut() {
+            starfield.resize(Size::new(width, height));
+        }
+        self.width = width;
+        self.height = height;
+        self.redraw_all = true;
+        // Resizing a canvas clears it, so a paused game has to put its banner back
+        if self.paused {
+            self.draw_paused();
+        }
+    }
+
+    /// Convert an x position in canvas pixels, like a mouse cursor's, to the game's own
+    /// coordinates for [`GameInput::MoveTo`] and [`GameInput::SteerTo`]
+    pub fn game_x(&self, canvas_x: f64) -> Distance {
+        canvas_x / f64::from(self.scale())
+    }
+
+    /// The steering controls held as of the last input taken in by a [`tick`](Self::tick)
+    pub fn input(&self) -> Input {
+        self.input
+    }
+
+    /// Scale the passage of game time: `0.5` is slow motion, `2.0` is fast-forward, and `0.0`
+    /// freezes the simulation while still taking in input, which always runs in real time.
+    /// Negative and non-finite scales are ignored.
+    pub fn set_time_scale(&mut self, scale: f64) {
+        if scale.is_finite() && scale >= 0.0 {
+            self.time_scale = scale;
+        }
+    }
+
+    /// The current game time multiplier, see [`set_time_scale`](Self::set_time_scale)
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Whether the ship is pinned against the left edge of its range, for cues or a player driving
+    /// the game from outside. A ship that wraps around is never pinned.
+    pub fn ship_at_left_bound(&self) -> bool {
+        self.ship.at_left_bound()
+    }
+
+    /// Whether the ship is pinned against the right edge of its range, see
+    /// [`ship_at_left_bound`](Self::ship_at_left_bound)
+    pub fn ship_at_right_bound(&self) -> bool {
+        self.ship.at_right_bound()
+    }
+
+    /// Bring in a row of reinforcements above the fleet's top row, one member for each of
+    /// `kinds` (indices into the enemy sprites), for a scripted event. Fails without adding
+    /// anything if any of the kinds has no sprite.
+    pub fn push_fleet_row(&mut self, kinds: &[usize]) -> Result<(), String> {
+        if let Some(kind) = kinds.iter().find(|&&kind| kind >= ENEMY_SPRITES) {
+            return Err(format!(
+                "no enemy sprite {}, there are {}",
+                kind, ENEMY_SPRITES
+            ));
+        }
+        self.enemies.push_row(kinds);
+        Ok(())
+    }
+
+    /// Take the fleet's row `row` (counting from the top) out of play, with the rows above it
+    /// closing up the gap
+    pub fn remove_fleet_row(&mut self, row: usize) {
+        self.enemies.remove_row(row);
+    }
+
+    /// Points scored so far
+    pub fn score(&self) -> u32 {
+        self.score.points
+    }
+
+    /// Lives the ship has left
+    pub fn lives(&self) -> u32 {
+        self.ship.lives
+    }
+
+    /// Queue `input` to be applied at the start of the next [`tick`](Self::tick), and record it
+    /// for the game's replay. While a replay is playing back, the player's inputs are ignored.
+    pub fn send_input(&mut self, input: GameInput) {
+        if !self.playback.is_empty() {
+            return;
+        }
+        // No game time passes before the next tick applies it, so it's recorded at the time it
+        // takes effect
+        self.replay.record(self.play_time, input);
+        self.inputs.push_back(input);
+    }
+
+    /// The seed the game started with and every input it's been sent, for playing it back with
+    /// [`GameConfig::replay`]
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Where the game stands now, for picking it back up with [`GameConfig::resume`]
+    pub fn snapshot(&self) -> Snapshot {
+        let ship = self.ship.inner.position();
+        Snapshot {
+            level: self.level,
+            score: self.score.points,
+            lives: self.ship.lives,
+            play_time: self.play_time,
+            ship: (ship.x(), ship.y()),
+            fleet: self.enemies.live_slots(),
+        }
+    }
+
+    /// Pick up from `snapshot`, taken from a game set up the same way
+    fn restore(&mut self, snapshot: &Snapshot) {
+        self.level = snapshot.level.max(1);
+        self.enemies = self.fleet_setup.build(self.width, self.level);
+        self.enemies.keep_live(&snapshot.fleet);
+        self.score.points = snapshot.score;
+        self.ship.lives = snapshot.lives;
+        self.play_time = snapshot.play_time;
+        let position = self.ship.inner.position_mut();
+        position.set(snapshot.ship.0, snapshot.ship.1);
+        // Back inside the ship's range, should the play area have shrunk since
+        position.offset(0.0, 0.0);
+    }
+
+    /// Apply the inputs being played back that are due by now, at the start of a frame the same as
+    /// the player's. Played back with the frames it was recorded at, a replay plays out exactly
+    /// the same; at another frame rate, inputs land on the first frame after their time.
+    fn play_back(&mut self, ts: TimeStamp) {
+        while let Some(event) = self.playback.front().copied() {
+            if event.ts > self.play_time {
+                break;
+            }
+            self.playback.pop_front();
+            self.replay.record(event.ts, event.input);
+            self.apply(event.input, ts);
+        }
+    }
+
+    /// Keep where the game stands, to be resumed with `data-resume`
+    fn save_snapshot(&self) {
+        if self.state == GameState::Playing {
+            self.saved.store_snapshot(&self.snapshot());
+        }
+    }
+
+    /// Tell the game whether its page has focus at time `ts`. Losing focus pauses the game, and
+    /// getting it back resumes a game that was paused that way.
+    pub fn set_focused(&mut self, focused: bool, ts: TimeStamp) {
+        if !focused {
+            // Keys let go of while the page is in the background never reach the canvas, so stop
+            // steering rather than drifting on after coming back
+            self.input = Input::default();
+            self.ship.target_x = None;
+        }
+        if !focused && !self.paused {
+            self.paused = true;
+            self.auto_paused = true;
+            self.draw_paused();
+            self.save_snapshot();
+        } else if focused && self.auto_paused && self.auto_resume {
+            self.paused = false;
+            self.auto_paused = false;
+            // Frames stop while the tab is hidden, so don't count the time away as one giant
+            // step
+            self.last_ts = ts;
+        }
+    }
+
+    /// Advance to time `ts`, in as many fixed steps as that takes, and draw the result
+    pub fn tick(&mut self, ts: TimeStamp) {
+        // Set ahead of input handling, which can spawn bullets
+        graphics::set_high_contrast(self.high_contrast);
+        while let Some(input) = self.inputs.pop_front() {
+            self.apply(input, ts);
+        }
+        self.play_back(ts);
+
+        let frame_ms = ts - self.last_ts;
+        self.last_ts = ts;
+        self.fps.record(frame_ms);
+        // The first frame's timestamp can land before the clock reading the game started from,
+        // or long after it, depending on how the browser schedules it; either way, nothing should
+        // jump. Time running backwards counts as none passing.
+        let elapsed = if frame_ms.is_finite() {
+            frame_ms.clamp(0.0, MAX_FRAME_MS)
+        } else {
+            0.0
+        };
+        // While paused or in a hit-stop nothing is cleared or drawn, so the last frame stays on
+        // screen; inputs above are still taken in as normal
+        if self.paused || self.hit_stop.tick(elapsed) {
+            return;
+        }
+        // Game time is used up in whole steps, with any remainder carried over to the next
+        // frame. A long stall only catches up by so many steps, rather than falling further and
+        // further behind trying to make up all of it.
+        self.unsimulated += elapsed * self.time_scale;
+        let mut steps = 0;
+        while self.unsimulated >= STEP_MS && steps < MAX_STEPS && !self.hit_stop.active() {
+            for event in self.update(ts, STEP_MS) {
+                self.handle(event);
+            }
+            self.unsimulated -= STEP_MS;
+            steps += 1;
+        }
+        if steps == MAX_STEPS {
+            self.unsimulated = self.unsimulated.min(STEP_MS);
+        }
+        self.render(ts);
+    }
+
+    fn apply(&mut self, input: GameInput, ts: TimeStamp) {
+        // Inputs come from the player pressing keys, which is when browsers allow audio to start
+        self.audio.unlock();
+        match input {
+            // Steering still follows the keys while paused, but nothing else happens, and the ship
+            // doesn't jump to wherever the mouse has wandered off to
+            GameInput::Fire | GameInput::Restart | GameInput::MoveTo(_) if self.paused => {}
+            // Any fire takes up the offer to continue
+            GameInput::Fire if matches!(self.state, GameState::Continue { .. }) => {
+                self.ship.lives = self.lives;
+                if self.continue_resets_score {
+                    self.score.reset();
+                }
+                self.state = GameState::Playing;
+                self.enemies.frozen = false;
+            }
+            GameInput::Left(_) | GameInput::Right(_) | GameInput::Up(_) | GameInput::Down(_) => {
+                self.input.apply(input);
+            }
+            GameInput::Fire => {
+                if self.ship.shoot(ts) {
+                    self.audio.play(Sound::Shoot);
+                }
+            }
+            GameInput::MoveTo(x) => self.ship.move_to(x),
+            GameInput::SteerTo(x) => self.ship.target_x = x,
+            // Neither overlay is measured for partial redraws, so the frame after one is turned
+            // off is drawn in full to clear it away
+            GameInput::ToggleFiringLine => {
+                self.show_firing_line = !self.show_firing_line;
+                self.redraw_all = true;
+            }
+            GameInput::ToggleFps => {
+                self.show_fps = !self.show_fps;
+                self.redraw_all = true;
+            }
+            GameInput::TogglePause => {
+                self.paused = !self.paused;
+                self.auto_paused = false;
+                if self.paused {
+                    self.draw_paused();
+                    self.save_snapshot();
+                }
+            }
+            GameInput::Restart => {
+                if matches!(self.state, GameState::GameOver | GameState::Victory) {
+                    self.restart(ts);
+                }
+            }
+        }
+    }
+
+    /// Play the sound for `event`, or put a finished game's score on record
+    fn handle(&mut self, event: GameEvent) {
+        match event {
+            GameEvent::Shot => self.audio.play(Sound::Shoot),
+            GameEvent::Hit => self.audio.play(Sound::Hit),
+            GameEvent::ShipDestroyed => self.audio.play(Sound::ShipDestroyed),
+            GameEvent::PowerUp => self.audio.play(Sound::PowerUp),
+            GameEvent::Finished(points) => {
+                self.high_score.record(points);
+                self.saved.store_replay(&self.replay);
+                self.saved.clear_snapshot();
+            }
+        }
+    }
+
+    /// Mark the frame left on screen while paused. It's drawn once on pausing, since nothing is
+    /// redrawn until the game resumes.
+    fn draw_paused(&mut self) {
+        // The banner is drawn over the last frame, so it has to be painted over on resuming
+        self.redraw_all = true;
+        let Some(surface) = &self.surface else {
+            return;
+        };
+        if let Some(backbuffer) = &surface.backbuffer {
+            backbuffer.resize(surface.canvas.width(), surface.canvas.height());
+        }
+        let context = surface.target();
+        // Resizing a canvas resets its transform, so the banner sets the zoom itself
+        graphics::set_zoom(context, self.scale());
+        context.set_font(HUD_FONT);
+        context.set_fill_style_str(HUD_COLOR);
+        context.set_text_align("center");
+        context
+            .fill_text("PAUSED", self.width / 2.0, self.height / 2.0)
+            .expect("fill_text");
+        surface.present();
+    }
+
+    /// Throw the current game away and set up a fresh one on the same canvas, with its clock
+    /// starting at `ts`
+    fn restart(&mut self, ts: TimeStamp) {
+        let game = match &self.surface {
+            Some(surface) => {
+                let config = GameConfig::from_canvas(&surface.canvas).for_restart();
+                Game::on_canvas(&surface.canvas, config, ts)
+            }
+            // Without a canvas to read them from again, the game is set up the way it was before
+            None => Game::with_config(self.width, self.height, self.config.clone().for_restart()),
+        };
+        match game {
+            Ok(mut game) => {
+                // Browsers limit how many audio contexts a page can have, so keep the one there is
+                game.audio = std::mem::take(&mut self.audio);
+                // Without storage to reload it from, the best score so far only lives here
+                game.high_score = std::mem::take(&mut self.high_score);
+                // Bindings set with set_key_bindings aren't in the canvas's attributes
+                game.keys = std::mem::take(&mut self.keys);
+                *self = game;
+            }
+            Err(err) => console::error_1(&err.into()),
+        }
+    }
+
+    /// Advance the game by one fixed step of `dt` game time, at time `ts`, returning what happened
+    /// along the way
+    ///
+    /// Every step is the same length however often frames come, so the game plays the same on
+    /// any display, and bullets move a short enough way between collision checks that they
+    /// can't pass straight through anything. A `dt` of zero or less, or that isn't a number, is
+    /// skipped rather than running the game backwards.
+    pub fn update(&mut self, ts: TimeStamp, dt: TimeStamp) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if !(dt > 0.0 && dt.is_finite()) {
+            return events;
+        }
+        let finished = matches!(self.state, GameState::GameOver | GameState::Victory);
+        if self.state == GameState::Playing {
+            self.play_time += dt;
+        }
+        // Flashes and the like are timed in game time, so they hold still through pauses
+        let now = self.play_time;
+        if let Some(starfield) = self.starfield.as_mut() {
+            starfield.update(dt);
+        }
+        // Barriers soak up bullets from both sides, wearing away where they're hit
+        for barrier in self.barriers.iter_mut() {
+            barrier.take_hits(self.ship.bullets_mut());
+            barrier.take_hits(self.enemies.bullets_mut());
+            if let Some(boss) = self.boss.as_mut() {
+                barrier.take_hits(boss.bullets_mut());
+            }
+        }
+        // Bullets are checked where the last step left them, so a member that's hit disappears
+        // in the same frame as the bullet that hit it
+        for enemy in self.enemies.take_hits(self.ship.bullets_mut(), now) {
+            self.score.add(self.enemies.points_for(enemy.kind));
+            self.explosions.push(Explosion::at(&enemy, &mut self.rng));
+            events.push(GameEvent::Hit);
+            if self.rng.next_f64() < self.drop_chance {
+                self.power_ups
+                    .push(PowerUp::dropped_by(&enemy, &mut self.rng));
+            }
+        }
+        if let Some(ufo) = self.ufo.as_mut() {
+            if ufo.take_hit(self.ship.bullets_mut()) {
+                self.score.add(Ufo::bonus(&mut self.rng));
+                events.push(GameEvent::Hit);
+                self.ufo = None;
+            }
+        }
+        if self.state == GameState::Playing {
+            let lives = self.ship.lives;
+            let mut alive = self.ship.take_hits(self.enemies.bullets_mut(), now);
+            if let Some(boss) = self.boss.as_mut() {
+                alive = self.ship.take_hits(boss.bullets_mut(), now) && alive;
+            }
+            if !alive {
+                self.state = GameState::out_of_lives(self.continue_seconds);
+            }
+            if self.ship.lives < lives {
+                self.hit_stop.trigger(HIT_STOP_FRAMES);
+                events.push(GameEvent::ShipDestroyed);
+            }
+            if !self.ship.catch(&mut self.power_ups, ts).is_empty() {
+                events.push(GameEvent::PowerUp);
+            }
+            if let Some(boss) = self.boss.as_mut() {
+                let health = boss.health;
+                if boss.take_hits(self.ship.bullets_mut(), now) {
+                    self.score.add(boss.stats.points);
+                    events.push(GameEvent::Hit);
+                    self.boss = None;
+                    self.next_level();
+                } else if boss.health < health {
+                    events.push(GameEvent::Hit);
+                }
+            } else if self.enemies.is_cleared() {
+                match self.fleet_setup.boss(self.level) {
+                    Some(stats) => {
+                        let rate = self.fleet_setup.difficulty.enemy_bullet_rate;
+                        let (left, right) = (MARGIN_X, self.width - MARGIN_X);
+                        self.boss = Some(Boss::new(stats, rate, self.fleet_setup.top, left, right));
+                    }
+                    None => self.next_level(),
+                }
+            }
+            // The fleet landing is the end of the game whatever lives are left, with no continue
+            if self
+                .enemies
+                .bottom()
+                .is_some_and(|bottom| bottom >= self.invasion_line)
+            {
+                self.ship.lives = 0;
+                self.state = GameState::GameOver;
+            }
+        }
+        let ctx = UpdateCtx {
+            ts,
+            offset_ts: dt,
+            input: &self.input,
+            bounds: Size::new(self.width, self.height),
+        };
+        let ship = self.ship.inner.position();
+        let ship_size = self.ship.inner.size();
+        let target = Position::new(
+            ship.x() + (ship_size.x() / 2.0),
+            ship.y() + (ship_size.y() / 2.0),
+        );
+        self.enemies.fire(dt, target, &mut self.rng);
+        self.enemies.update(&ctx);
+        if let Some(boss) = self.boss.as_mut() {
+            boss.fire(dt, &mut self.rng);
+            boss.update(&ctx);
+        }
+        graphics::update_all(&mut self.explosions, &ctx);
+        graphics::update_all(&mut self.power_ups, &ctx);
+        // The UFO flies on to the edge whatever happens, but only sets off while playing
+        if let Some(ufo) = self.ufo.as_mut() {
+            if !ufo.update(&ctx) {
+                self.ufo = None;
+            }
+        } else if let (Some(interval), GameState::Playing) = (self.ufo_interval, self.state) {
+            self.ufo_countdown -= dt;
+            if self.ufo_countdown <= 0.0 {
+                self.ufo_countdown = interval;
+                let from_left = self.rng.next_f64() < 0.5;
+                self.ufo = Some(Ufo::new(UFO_Y, self.width, from_left));
+            }
+        }
+        // The ship is gone once it's out of lives, until the player continues
+        if self.state == GameState::Playing {
+            // A shot queued during the cooldown can go off here rather than on the key press
+            let last_shot_ts = self.ship.last_shot_ts;
+            self.ship.update(&ctx);
+            if self.ship.last_shot_ts != last_shot_ts {
+                events.push(GameEvent::Shot);
+            }
+        }
+
+        if let GameState::Continue { seconds_left } = &mut self.state {
+            *seconds_left -= dt / 1000.0;
+            if *seconds_left <= 0.0 {
+                self.state = GameState::GameOver;
+            }
+        }
+        if self.state != GameState::Playing {
+            self.enemies.frozen = true;
+        }
+        if let Some(boss) = self.boss.as_mut() {
+            boss.frozen = self.state != GameState::Playing;
+        }
+        // A finished game's score goes on record
+        if !finished && matches!(self.state, GameState::GameOver | GameState::Victory) {
+            events.push(GameEvent::Finished(self.score.points));
+        }
+        events
+    }
+
+    /// Move on from a cleared level: to a fresh, faster fleet on the next one, keeping the score,
+    /// lives, and barriers, or to victory if that was the last
+    fn next_level(&mut self) {
+        if self.levels.is_some_and(|levels| self.level >= levels) {
+            self.state = GameState::Victory;
+        } else {
+            self.level += 1;
+            self.enemies = self.fleet_setup.build(self.width, self.level);
+            // A campaign level with bunkers of its own brings in fresh ones where it wants them,
+            // while replaying the last level keeps what's left of the old ones
+            let bunkers = self.fleet_setup.bunkers(self.level);
+            let new_level = self.level as usize <= self.fleet_setup.levels.len();
+            if new_level && !bunkers.is_empty() {
+                self.barriers = bunkers.iter().map(|_| Barrier::new()).collect();
+                Barrier::arrange(
+                    &mut self.barriers,
+                    bunkers,
+                    MARGIN_X,
+                    self.width - MARGIN_X,
+                    self.invasion_line - BARRIER_GAP,
+                );
+            }
+        }
+    }
+
+    /// Draw everything where the last [`update`](Self::update) left it, at time `ts`
+    pub fn render(&mut self, ts: TimeStamp) {
+        let Some(surface) = &self.surface else {
+            return;
+        };
+        let resized = surface.backbuffer.as_ref().is_some_and(|backbuffer| {
+            backbuffer.resize(surface.canvas.width(), surface.canvas.height())
+        });
+        let context = surface.target().clone();
+        // Rendering options are global, so each game sets its own before drawing a frame
+        graphics::set_subpixel(&context, self.subpixel);
+        graphics::set_blend(self.blend || surface.backbuffer.is_some());
+        graphics::set_zoom(&context, self.scale());
+        graphics::set_outline(self.outline);
+        graphics::set_now(self.play_time);
+
+        // The frame is laid out without drawing it first, to compare against the last one. Stars
+        // and the firing line aren't measured, and move every frame anyway, so with them on (or
+        // with a banner up) everything is redrawn.
+        let last = std::mem::take(&mut self.drawn);
+        let full = self.full_redraw
+            || self.redraw_all
+            || resized
+            || self.starfield.is_some()
+            || self.show_firing_line
+            || self.show_fps
+            || self.state != GameState::Playing;
+        if !self.full_redraw {
+            self.drawn = graphics::measure(|| self.draw_scene(&context, ts));
+        }
+        if full {
+            self.clear(&context);
+            self.draw_scene(&context, ts);
+        } else {
+            // Everything is drawn again, but clipped to just what changed (and the HUD, whose
+            // text isn't measured), so the untouched parts of the canvas are left alone
+            context.save();
+            context.begin_path();
+            context.rect(0.0, 0.0, self.width, HUD_HEIGHT);
+            let changed = graphics::changed_rects(&last, &self.drawn);
+            for (position, size) in graphics::merge_overlapping(&changed) {
+                // Sprites can be snapped up to a pixel away from their position
+                context.rect(
+                    position.x() - 1.0,
+                    position.y() - 1.0,
+                    size.x() + 2.0,
+                    size.y() + 2.0,
+                );
+            }
+            context.clip();
+            self.clear(&context);
+            self.draw_scene(&context, ts);
+            context.restore();
+        }
+        self.redraw_all = self.state != GameState::Playing;
+
+        let banner = match self.state {
+            GameState::Playing => None,
+            GameState::Continue { seconds_left } => {
+                Some(format!("CONTINUE? {}", seconds_left.ceil()))
+            }
+            GameState::GameOver => Some("GAME OVER".to_string()),
+            GameState::Victory => Some("YOU WIN".to_string()),
+        };
+        if let Some(banner) = banner {
+            context.set_font(HUD_FONT);
+            context.set_fill_style_str(HUD_COLOR);
+            context.set_text_align("center");
+            context
+                .fill_text(&banner, self.width / 2.0, self.height / 2.0)
+                .expect("fill_text");
+        }
+
+        // The best shown climbs along with the score once it's being beaten
+        self.score.high = Some(self.high_score.best().max(self.score.points));
+        self.score.draw(&context);
+        context.set_font(HUD_FONT);
+        context.set_fill_style_str(HUD_COLOR);
+        context.set_text_align("center");
+        context
+            .fill_text(&format!("LEVEL {}", self.level), self.width / 2.0, 24.0)
+            .expect("fill_text");
+        if self.show_time {
+            context.set_font(HUD_FONT);
+            context.set_fill_style_str(HUD_COLOR);
+            context.set_text_align("right");
+            context
+                .fill_text(
+                    &graphics::format_time(self.play_time),
+                    self.width - MARGIN_X,
+                    24.0,
+                )
+                .expect("fill_text");
+        }
+        if self.show_fps {
+            self.fps.position.set(MARGIN_X, self.height - 12.0);
+            self.fps.draw(&context);
+        }
+        if let Some(surface) = &self.surface {
+            surface.present();
+        }
+    }
+
+    /// Paint the background over the whole play area, or as much of it as `context` is clipped to
+    fn clear(&self, context: &CanvasRenderingContext2d) {
+        match self.background.as_deref() {
+            Some(color) => {
+                context.set_fill_style_str(color);
+                context.fill_rect(0.0, 0.0, self.width, self.height);
+            }
+            None => context.clear_rect(0.0, 0.0, self.width, self.height),
+        }
+    }
+
+    /// Draw everything in play at time `ts` with `context`, below the banner and HUD
+    fn draw_scene(&mut self, context: &CanvasRenderingContext2d, ts: TimeStamp) {
+        if let Some(starfield) = self.starfield.as_mut() {
+            starfield.draw(context);
+        }
+        graphics::draw_all(&mut self.barriers, context);
+        self.enemies.draw(context);
+        if let Some(boss) = self.boss.as_mut() {
+            boss.draw(context);
+        }
+        graphics::draw_all(&mut self.explosions, context);
+        graphics::draw_all(&mut self.power_ups, context);
+        if let Some(ufo) = self.ufo.as_mut() {
+            ufo.draw(context);
+        }
+        if self.state == GameState::Playing {
+            self.ship.draw(context);
+            if self.show_firing_line {
+                self.ship.draw_firing_line(context, &self.enemies);
+            }
+            if self.show_cooldown {
+                self.ship.draw_cooldown_bar(context, ts);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "benchmark")]
+impl Game {
+    /// Replace the fleet with a huge one and take the limits off the ship's firing, to load the
+    /// game up for [`start_benchmark`](crate::bench::start_benchmark)
+    pub(crate) fn stress(&mut self, rows: usize, columns: usize) {
+        let rate = self.enemies.horizontal_rate;
+        self.enemies = FleetBuilder::default()
+            .rows(rows)
+            .columns(columns)
+            .spacing(4.0)
+            .bounds(MARGIN_X, self.width - MARGIN_X)
+            .rate(rate)
+            .build();
+        self.ship.cooldown_ms = 0.0;
+        self.ship.max_bullets = usize::MAX;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::Vec2;
+
+    /// A game with no canvas, and the same randomness every time
+    fn game() -> Game {
+        let config = GameConfig {
+            seed: 1,
+            ..GameConfig::default()
+        };
+        Game::with_config(600.0, 480.0, config).unwrap()
+    }
+
+    /// Move `game` on by `steps` whole steps, collecting what happened along the way
+    fn run(game: &mut Game, steps: u32) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        for _ in 0..steps {
+            events.extend(game.update(game.play_time, STEP_MS));
+        }
+        events
+    }
+
+    #[test]
+    fn steering_follows_held_controls() {
+        let mut input = Input::default();
+        assert_eq!((input.x(), input.y()), (0.0, 0.0));
+        assert!(input.apply(GameInput::Left(true)));
+        assert!(input.apply(GameInput::Down(true)));
+        assert_eq!((input.x(), input.y()), (-1.0, 1.0));
+        assert!(input.apply(GameInput::Left(false)));
+        assert!(input.apply(GameInput::Up(true)));
+        assert!(input.apply(GameInput::Down(false)));
+        assert_eq!((input.x(), input.y()), (0.0, -1.0));
+    }
+
+    #[test]
+    fn opposite_controls_cancel_out() {
+        let mut input = Input::default();
+        input.apply(GameInput::Left(true));
+        input.apply(GameInput::Right(true));
+        assert_eq!(input.x(), 0.0);
+        input.apply(GameInput::Up(true));
+        input.apply(GameInput::Down(true));
+        assert_eq!(input.y(), 0.0);
+    }
+
+    #[test]
+    fn letting_go_of_one_opposite_steers_toward_the_other() {
+        let mut input = Input::default();
+        input.apply(GameInput::Right(true));
+        input.apply(GameInput::Left(true));
+        input.apply(GameInput::Left(false));
+        assert_eq!(input.x(), 1.0);
+        input.apply(GameInput::Left(true));
+        input.apply(GameInput::Right(false));
+        assert_eq!(input.x(), -1.0);
+
+        input.apply(GameInput::Up(true));
+        input.apply(GameInput::Down(true));
+        input.apply(GameInput::Up(false));
+        assert_eq!(input.y(), 1.0);
+    }
+
+    #[test]
+    fn one_off_actions_leave_steering_alone() {
+        let mut input = Input::default();
+        input.apply(GameInput::Right(true));
+        for action in [GameInput::Fire, GameInput::TogglePause, GameInput::Restart] {
+            assert!(!input.apply(action));
+        }
+        assert_eq!(
+            input,
+            Input {
+                right: true,
+                ..Input::default()
+            }
+        );
+    }
+
+    #[test]
+    fn too_small_a_play_area_is_refused() {
+        for (width, height) in [(50.0, 480.0), (600.0, 90.0), (Distance::NAN, 480.0)] {
+            assert!(Game::with_config(width, height, GameConfig::default()).is_err());
+        }
+    }
+
+    #[test]
+    fn held_controls_move_the_ship() {
+        let mut game = game();
+        run(&mut game, 1);
+        let start = game.ship.inner.position().x();
+        game.apply(GameInput::Right(true), game.play_time);
+        run(&mut game, 10);
+        let moved = game.ship.inner.position().x();
+        assert!(moved > start);
+
+        game.apply(GameInput::Right(false), game.play_time);
+        game.apply(GameInput::Left(true), game.play_time);
+        run(&mut game, 5);
+        assert!(game.ship.inner.position().x() < moved);
+    }
+
+    #[test]
+    fn shooting_a_member_destroys_it_and_scores() {
+        let mut game = game();
+        // Hold the fleet still, so the shot can't miss as it goes by
+        game.enemies.frozen = true;
+        let members = game.enemies.member_count();
+        let (_, _, target) = game.enemies.live_members().last().unwrap();
+        let target_x = target.inner.position().x() + (target.inner.size().x() / 2.0);
+        game.ship.move_to(target_x);
+        game.apply(GameInput::Fire, game.play_time);
+
+        let mut steps = 0;
+        while !run(&mut game, 1).contains(&GameEvent::Hit) {
+            steps += 1;
+            assert!(steps < 120, "the shot never landed");
+        }
+        assert_eq!(game.enemies.member_count(), members - 1);
+        assert!(game.score.points > 0);
+        assert_eq!(game.explosions.len(), 1);
+    }
+
+    #[test]
+    fn losing_the_last_life_ends_the_game() {
+        let mut game = game();
+        run(&mut game, 1);
+        game.ship.lives = 1;
+        let position = game.ship.inner.position();
+        game.enemies
+            .bullets_mut()
+            .fire(position, Vec2::new(0.0, 0.1));
+
+        let events = run(&mut game, 1);
+        assert!(events.contains(&GameEvent::ShipDestroyed));
+        assert!(events.contains(&GameEvent::Finished(0)));
+        assert_eq!(game.state, GameState::GameOver);
+        // Game time stops once it's over
+        let play_time = game.play_time;
+        assert!(!run(&mut game, 10).contains(&GameEvent::Finished(0)));
+        assert_eq!(game.play_time, play_time);
+
+        game.apply(GameInput::Restart, play_time);
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.ship.lives, Difficulty::Normal.settings().lives);
+    }
+
+    #[test]
+    fn ticks_run_whole_steps_of_game_time() {
+        let mut game = game();
+        // Time only starts passing from the first frame
+        game.tick(1000.0);
+        assert_eq!(game.play_time, 0.0);
+        game.tick(1040.0);
+        assert_eq!(game.play_time, 2.0 * STEP_MS);
+
+        game.send_input(GameInput::TogglePause);
+        game.tick(1080.0);
+        assert_eq!(game.play_time, 2.0 * STEP_MS);
+    }
+
+    /// Stand under the middle of the fleet and keep firing, one frame at a time, until something
+    /// is hit, returning how many frames that took
+    fn play_until_scoring(game: &mut Game) -> u32 {
+        let mut frames = 0;
+        game.tick(0.0);
+        game.send_input(GameInput::MoveTo(300.0));
+        while game.score() == 0 {
+            frames += 1;
+            assert!(frames < 600, "nothing was hit");
+            game.send_input(GameInput::Fire);
+            game.tick(f64::from(frames) * STEP_MS);
+        }
+        frames
+    }
+
+    #[test]
+    fn a_seeded_game_plays_out_the_same_through_its_inputs() {
+        let mut first = game();
+        let members = first.enemies.member_count();
+        assert_eq!((first.score(), first.lives()), (0, Ship::DEFAULT_LIVES));
+        let frames = play_until_scoring(&mut first);
+        assert_eq!(first.enemies.member_count(), members - 1);
+        assert_eq!(first.lives(), Ship::DEFAULT_LIVES);
+
+        // The same seed and inputs play out the same way again
+        let mut again = game();
+        assert_eq!(play_until_scoring(&mut again), frames);
+        assert_eq!(again.score(), first.score());
+    }
+
+    #[test]
+    fn time_scale_speeds_up_and_slows_down_game_time() {
+        let mut game = game();
+        game.tick(0.0);
+        game.set_time_scale(0.5);
+        game.tick(40.0);
+        assert_eq!(game.play_time, STEP_MS);
+
+        // Frozen, inputs are still taken in
+        game.set_time_scale(0.0);
+        game.send_input(GameInput::Right(true));
+        game.tick(80.0);
+        assert_eq!(game.play_time, STEP_MS);
+        assert!(game.input().right);
+
+        for unusable in [-1.0, f64::NAN, f64::INFINITY] {
+            game.set_time_scale(unusable);
+            assert_eq!(game.time_scale(), 0.0);
+        }
+        game.set_time_scale(2.0);
+        game.tick(100.0);
+        assert_eq!(game.play_time, 3.0 * STEP_MS);
+    }
+
+    #[test]
+    fn holding_a_direction_pins_the_ship_against_that_edge() {
+        let mut game = game();
+        game.tick(0.0);
+        let mut ts = 0.0;
+        let mut hold = |game: &mut Game, input: GameInput| {
+            game.send_input(input);
+            for _ in 0..20 {
+                ts += 100.0;
+                game.tick(ts);
+            }
+        };
+        hold(&mut game, GameInput::Left(true));
+        assert!(game.ship_at_left_bound() && !game.ship_at_right_bound());
+
+        game.send_input(GameInput::Left(false));
+        hold(&mut game, GameInput::Right(true));
+        assert!(!game.ship_at_left_bound() && game.ship_at_right_bound());
+    }
+
+    #[test]
+    fn fleet_rows_can_be_brought_in_and_taken_out() {
+        let mut game = game();
+        let members = game.enemies.member_count();
+        game.push_fleet_row(&[0, 1, 2, 3]).unwrap();
+        assert_eq!(game.enemies.member_count(), members + 4);
+        assert!(game.push_fleet_row(&[0, ENEMY_SPRITES]).is_err());
+        assert_eq!(game.enemies.member_count(), members + 4);
+
+        game.remove_fleet_row(0);
+        assert_eq!(game.enemies.member_count(), members);
+    }
+
+    #[test]
+    fn sent_inputs_are_recorded_and_play_back_the_same() {
+        let mut first = game();
+        let frames = play_until_scoring(&mut first);
+        let replay = Replay::from_bytes(&first.replay().to_bytes()).unwrap();
+        assert_eq!(replay.seed, 1);
+        // A move under the fleet, then a shot every frame
+        assert_eq!(replay.events.len(), frames as usize + 1);
+        assert_eq!(replay.events[0].input, GameInput::MoveTo(300.0));
+
+        // The replay's seed wins out, and the player's inputs wait until it's over
+        let config = GameConfig {
+            seed: 2,
+            replay: Some(replay.clone()),
+            ..GameConfig::default()
+        };
+        let mut again = Game::with_config(600.0, 480.0, config).unwrap();
+        again.tick(0.0);
+        for frame in 1..=frames {
+            again.send_input(GameInput::Left(true));
+            again.tick(f64::from(frame) * STEP_MS);
+        }
+        assert_eq!(again.score(), first.score());
+        assert_eq!(again.enemies.member_count(), first.enemies.member_count());
+        assert_eq!(again.replay(), &replay);
+        assert!(!again.input().left);
+
+        // Once it's over, the player takes over
+        again.send_input(GameInput::Left(true));
+        again.tick(f64::from(frames + 1) * STEP_MS);
+        assert!(again.input().left);
+    }
+
+    #[test]
+    fn a_snapshot_picks_the_game_back_up() {
+        let mut first = game();
+        first.send_input(GameInput::Left(true));
+        play_until_scoring(&mut first);
+        first.next_level();
+        first.enemies.members[0][1] = None;
+        first.ship.lives -= 1;
+        let snapshot = Snapshot::from_bytes(&first.snapshot().to_bytes()).unwrap();
+        assert_eq!(snapshot, first.snapshot());
+
+        let config = GameConfig {
+            resume: Some(snapshot.clone()),
+            ..GameConfig::default()
+        };
+        let resumed = Game::with_config(600.0, 480.0, config.clone()).unwrap();
+        assert_eq!(resumed.snapshot(), snapshot);
+        assert_eq!(resumed.level, 2);
+        assert_eq!(resumed.lives(), Ship::DEFAULT_LIVES - 1);
+        assert_eq!(resumed.enemies.member_count(), first.enemies.member_count());
+
+        // Starting over once it's done starts from scratch
+        let restarted = Game::with_config(600.0, 480.0, config.for_restart()).unwrap();
+        assert_eq!((restarted.level, restarted.score()), (1, 0));
+    }
+
+    #[test]
+    fn a_level_places_its_bunkers() {
+        let level = level::load_level(r#"{ "fleet": [[0, 1]], "bunkers": [120, 300, 480] }"#);
+        let config = GameConfig {
+            level: Some(level.unwrap()),
+            ..GameConfig::default()
+        };
+        let game = Game::with_config(600.0, 480.0, config).unwrap();
+        let centers: Vec<Distance> = game
+            .barriers
+            .iter()
+            .map(|barrier| (barrier.inner.position().x() + barrier.inner.extent().x()) / 2.0)
+            .collect();
+        assert_eq!(centers, [120.0, 300.0, 480.0]);
+        assert_eq!(game.enemies.member_count(), 2);
+    }
+
+    #[test]
+    fn a_campaign_plays_its_levels_in_turn() {
+        let campaign = level::load_campaign(
+            r#"[
+                { "fleet": [[0, 1, 2]] },
+                { "fleet": [[3], [3]], "bunkers": [200, 400] }
+            ]"#,
+        );
+        let config = GameConfig {
+            campaign: Some(campaign.unwrap()),
+            ..GameConfig::default()
+        };
+        let mut game = Game::with_config(600.0, 480.0, config).unwrap();
+        assert_eq!((game.levels, game.enemies.member_count()), (Some(2), 3));
+        assert!(game.barriers.is_empty());
+
+        game.next_level();
+        assert_eq!((game.level, game.enemies.member_count()), (2, 2));
+        assert_eq!(game.barriers.len(), 2);
+        game.next_level();
+        assert_eq!(game.state, GameState::Victory);
+    }
+}
@@ -0,0 +1,86 @@
+//! Game controller support, through the browser's Gamepad API
+//!
+//! Browsers don't send events as a controller's sticks and buttons change, so the controller has
+//! to be read once per frame with [`GamepadPoller::poll`]. The gamepad list is read afresh every
+//! time, which also takes care of controllers being plugged in or pulled out mid-game.
+
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton};
+
+use crate::game::GameInput;
+use crate::geom::Distance;
+
+/// Turns the first connected controller's left stick and bottom face button into
+/// [`GameInput`]s, sent only as they change so the keyboard can be used alongside
+#[derive(Default)]
+pub(crate) struct GamepadPoller {
+    /// Which way the stick was last pushed, as `-1.0`, `0.0`, or `1.0`
+    heading: Distance,
+    fire_held: bool,
+}
+
+impl GamepadPoller {
+    /// How far the stick has to be pushed before it counts, so a worn stick resting slightly off
+    /// center doesn't creep the ship along
+    const DEADZONE: f64 = 0.25;
+    /// Left stick x in the browser's standard controller mapping
+    const AXIS_X: u32 = 0;
+    /// The bottom face button (A, or cross) in the standard mapping
+    const FIRE_BUTTON: u32 = 0;
+
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the controller, returning the inputs for whatever changed since the last poll. A
+    /// controller that's gone lets go of everything it was holding.
+    pub(crate) fn poll(&mut self) -> Vec<GameInput> {
+        let (axis_x, fire_held) = Self::gamepad().map_or((0.0, false), |gamepad| {
+            let axis_x = gamepad.axes().get(Self::AXIS_X).as_f64().unwrap_or(0.0);
+            let fire_held = gamepad
+                .buttons()
+                .get(Self::FIRE_BUTTON)
+                .dyn_into::<GamepadButton>()
+                .is_ok_and(|button| button.pressed());
+            (axis_x, fire_held)
+        });
+
+        let mut inputs = Vec::new();
+        let heading = if axis_x.abs() < Self::DEADZONE {
+            0.0
+        } else {
+            axis_x.signum()
+        };
+        if heading != self.heading {
+            // Let go of the old direction before pushing the new one, as a player switching keys
+            // would
+            if self.heading < 0.0 {
+                inputs.push(GameInput::Left(false));
+            } else if self.heading > 0.0 {
+                inputs.push(GameInput::Right(false));
+            }
+            if heading < 0.0 {
+                inputs.push(GameInput::Left(true));
+            } else if heading > 0.0 {
+                inputs.push(GameInput::Right(true));
+            }
+            self.heading = heading;
+        }
+        // Like a key, a held button fires once rather than repeatedly
+        if fire_held && !self.fire_held {
+            inputs.push(GameInput::Fire);
+        }
+        self.fire_held = fire_held;
+        inputs
+    }
+
+    /// The first connected controller, if there is one. The list has gaps (`null`s) where
+    /// controllers have been disconnected.
+    fn gamepad() -> Option<Gamepad> {
+        let gamepads = web_sys::window()?.navigator().get_gamepads().ok()?;
+        gamepads
+            .iter()
+            .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+            .find(Gamepad::connected)
+    }
+}
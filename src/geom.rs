@@ -1,3 +1,5 @@
+use std::f64::consts::TAU;
+
 pub type Distance = f64;
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -42,10 +44,36 @@ pub trait XY {
         self.set_y(y);
     }
 
+    /// Move by (`dx`, `dy`) as is, without going through the offset strategies like
+    /// [`offset`](Self::offset) does
+    fn add(&mut self, dx: Distance, dy: Distance) {
+        self.set(self.x() + dx, self.y() + dy);
+    }
+
+    /// Length of the vector from the origin to (`x`, `y`)
+    fn length(&self) -> Distance {
+        self.x().hypot(self.y())
+    }
+
+    /// Straight-line distance from here to `other`
+    fn distance_to(&self, other: &impl XY) -> Distance {
+        (other.x() - self.x()).hypot(other.y() - self.y())
+    }
+
+    /// A copy of the strategy currently applied to offsets along the x axis
+    fn offset_x(&self) -> OffsetStrategy {
+        self.get_coordinates().x_strategy
+    }
+
     fn set_offset_x(&mut self, strategy: OffsetStrategy) {
         self.get_coordinates_mut().x_strategy = strategy;
     }
 
+    /// A copy of the strategy currently applied to offsets along the y axis
+    fn offset_y(&self) -> OffsetStrategy {
+        self.get_coordinates().y_strategy
+    }
+
     fn set_offset_y(&mut self, strategy: OffsetStrategy) {
         self.get_coordinates_mut().y_strategy = strategy;
     }
@@ -80,6 +108,23 @@ impl Position {
     }
 }
 
+impl Position {
+    /// Unit vector pointing from this position toward `other`, or a zero vector if they coincide
+    pub fn direction_to(&self, other: Position) -> Vec2 {
+        let delta = self.delta_to(other);
+        let length = delta.length();
+        if length == 0.0 {
+            Vec2::default()
+        } else {
+            Vec2::new(delta.x / length, delta.y / length)
+        }
+    }
+
+    fn delta_to(&self, other: Position) -> Vec2 {
+        Vec2::new(other.x() - self.x(), other.y() - self.y())
+    }
+}
+
 impl XY for Position {
     fn get_coordinates(&self) -> Coordinates {
         self.0
@@ -90,6 +135,23 @@ impl XY for Position {
     }
 }
 
+/// A displacement or direction between two points, rather than a location
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: Distance,
+    pub y: Distance,
+}
+
+impl Vec2 {
+    pub fn new(x: Distance, y: Distance) -> Self {
+        Self { x, y }
+    }
+
+    pub fn length(&self) -> Distance {
+        self.x.hypot(self.y)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Size(Coordinates);
 
@@ -122,6 +184,45 @@ pub trait Rect {
         let size = self.size();
         Position::new(origin.x() + size.x(), origin.y() + size.y())
     }
+
+    /// The smallest rectangle enclosing both `self` and `other`, as its origin and size
+    fn union(&self, other: &dyn Rect) -> (Position, Size) {
+        let (origin, extent) = (self.position(), self.extent());
+        let (other_origin, other_extent) = (other.position(), other.extent());
+        let x = origin.x().min(other_origin.x());
+        let y = origin.y().min(other_origin.y());
+        let size = Size::new(
+            extent.x().max(other_extent.x()) - x,
+            extent.y().max(other_extent.y()) - y,
+        );
+        (Position::new(x, y), size)
+    }
+
+    /// Area of the region covered by both `self` and `other`, zero if they don't overlap
+    fn intersection_area(&self, other: &dyn Rect) -> Distance {
+        let (origin, extent) = (self.position(), self.extent());
+        let (other_origin, other_extent) = (other.position(), other.extent());
+        let width = extent.x().min(other_extent.x()) - origin.x().max(other_origin.x());
+        let height = extent.y().min(other_extent.y()) - origin.y().max(other_origin.y());
+        width.max(0.0) * height.max(0.0)
+    }
+
+    /// Whether `self` and `other` overlap on both axes; rectangles that only share an edge don't
+    fn intersects(&self, other: &dyn Rect) -> bool {
+        let (origin, extent) = (self.position(), self.extent());
+        let (other_origin, other_extent) = (other.position(), other.extent());
+        origin.x() < other_extent.x()
+            && extent.x() > other_origin.x()
+            && origin.y() < other_extent.y()
+            && extent.y() > other_origin.y()
+    }
+
+    /// Whether the point `(x, y)` lies inside the rectangle, counting its top and left edges but
+    /// not its bottom and right ones, so a point is never inside two rectangles that share an edge
+    fn contains_point(&self, x: Distance, y: Distance) -> bool {
+        let (origin, extent) = (self.position(), self.extent());
+        (origin.x()..extent.x()).contains(&x) && (origin.y()..extent.y()).contains(&y)
+    }
 }
 
 impl XY for dyn Rect {
@@ -135,7 +236,7 @@ impl XY for dyn Rect {
 }
 
 #[non_exhaustive]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum OffsetStrategy {
     Linear,
     Limit {
@@ -147,6 +248,31 @@ pub enum OffsetStrategy {
         max: Distance,
         direction: Distance,
     },
+    /// Leaving past `max` comes back in at `min`, and the other way around
+    Wrap {
+        min: Distance,
+        max: Distance,
+    },
+    /// Kept between `min` and `max` like [`Limit`](Self::Limit), but gliding: each offset only
+    /// moves `velocity` part of the way from the last distance moved toward the one asked for,
+    /// closing `acceleration` (from `0.0` to `1.0`) of the gap, so movement speeds up from a
+    /// standstill and coasts to a stop once the offsets stop
+    EaseInOut {
+        min: Distance,
+        max: Distance,
+        velocity: Distance,
+        acceleration: f64,
+    },
+    /// Swings back and forth around `center`, up to `amplitude` either side, once every `period`
+    /// of offset. Offsets only advance the swing, like time passing, so where it ends up doesn't
+    /// depend on the value it was at.
+    Oscillate {
+        center: Distance,
+        amplitude: Distance,
+        period: Distance,
+        /// How far through the swing it is, in radians
+        phase: f64,
+    },
 }
 
 impl Default for OffsetStrategy {
@@ -165,37 +291,469 @@ impl OffsetStrategy {
     }
 
     pub fn cycle(min: Distance, max: Distance) -> Self {
+        Self::cycle_with_direction(min, max, 1.0)
+    }
+
+    pub fn wrap(min: Distance, max: Distance) -> Self {
+        Self::Wrap { min, max }
+    }
+
+    pub fn ease_in_out(min: Distance, max: Distance, acceleration: f64) -> Self {
+        Self::EaseInOut {
+            min,
+            max,
+            velocity: 0.0,
+            acceleration: acceleration.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn oscillate(center: Distance, amplitude: Distance, period: Distance) -> Self {
+        Self::Oscillate {
+            center,
+            amplitude: amplitude.abs(),
+            period,
+            phase: 0.0,
+        }
+    }
+
+    /// Like [`cycle`](Self::cycle), but initially moving toward `min` if `direction` is negative
+    pub fn cycle_with_direction(min: Distance, max: Distance, direction: Distance) -> Self {
         Self::Cycle {
             min,
             max,
-            direction: 1.0,
+            direction: 1.0_f64.copysign(direction),
+        }
+    }
+
+    /// The `(min, max)` range the strategy keeps values within, if it has one
+    pub fn bounds(&self) -> Option<(Distance, Distance)> {
+        match *self {
+            OffsetStrategy::Linear => None,
+            OffsetStrategy::Limit { min, max }
+            | OffsetStrategy::Cycle { min, max, .. }
+            | OffsetStrategy::Wrap { min, max }
+            | OffsetStrategy::EaseInOut { min, max, .. } => Some((min, max)),
+            OffsetStrategy::Oscillate {
+                center, amplitude, ..
+            } => Some((center - amplitude, center + amplitude)),
+        }
+    }
+
+    /// The same strategy, with any movement state kept, but constrained to new bounds
+    pub fn with_bounds(self, min: Distance, max: Distance) -> Self {
+        match self {
+            OffsetStrategy::Linear => OffsetStrategy::Linear,
+            OffsetStrategy::Limit { .. } => OffsetStrategy::Limit { min, max },
+            OffsetStrategy::Wrap { .. } => OffsetStrategy::Wrap { min, max },
+            OffsetStrategy::EaseInOut {
+                velocity,
+                acceleration,
+                ..
+            } => OffsetStrategy::EaseInOut {
+                min,
+                max,
+                velocity,
+                acceleration,
+            },
+            OffsetStrategy::Cycle { direction, .. } => OffsetStrategy::Cycle {
+                min,
+                max,
+                direction,
+            },
+            OffsetStrategy::Oscillate { period, phase, .. } => OffsetStrategy::Oscillate {
+                center: (min + max) / 2.0,
+                amplitude: (max - min).max(0.0) / 2.0,
+                period,
+                phase,
+            },
         }
     }
 
     pub fn offset(&mut self, current: Distance, offset: Distance) -> Distance {
         match self {
-            OffsetStrategy::Linear => (current + offset),
+            OffsetStrategy::Linear => current + offset,
             OffsetStrategy::Limit { min, max } => (current + offset).min(*max).max(*min),
+            OffsetStrategy::Wrap { min, max } => {
+                if *max <= *min {
+                    return *min;
+                }
+                let range = *max - *min;
+                let wrapped = (current + offset - *min).rem_euclid(range);
+                // A sliver below `min` rounds up to the whole range, which would land on `max`
+                *min + if wrapped < range { wrapped } else { 0.0 }
+            }
+            OffsetStrategy::EaseInOut {
+                min,
+                max,
+                velocity,
+                acceleration,
+            } => {
+                *velocity += (offset - *velocity) * *acceleration;
+                let next = (current + *velocity).min(*max).max(*min);
+                // Running into a bound stops the glide rather than pushing against it
+                if next != current + *velocity {
+                    *velocity = 0.0;
+                }
+                next
+            }
+            OffsetStrategy::Oscillate {
+                center,
+                amplitude,
+                period,
+                phase,
+            } => {
+                // A zero period would be infinitely fast, so it stays put in the middle instead
+                if *period > 0.0 {
+                    *phase = (*phase + (offset / *period) * TAU).rem_euclid(TAU);
+                }
+                *center + (*amplitude * phase.sin())
+            }
             OffsetStrategy::Cycle {
                 min,
                 max,
                 direction,
             } => {
-                let mut result = current + offset.copysign(*direction);
-                loop {
-                    if result > *max {
-                        let offset = result - *max;
-                        result -= offset;
-                        *direction = -1.0;
-                    } else if result < *min {
-                        let offset = *min - result;
-                        result = *min + offset;
-                        *direction = 1.0;
-                    } else {
-                        break result;
-                    }
+                // An empty or inverted range leaves nowhere to move
+                if *max <= *min {
+                    return *min;
+                }
+                // Bouncing back and forth is a triangle wave: unfold it into a phase along one
+                // full lap of `2 * range`, advance the phase, and fold it back, which takes the
+                // same time however many laps the offset covers
+                let range = *max - *min;
+                let along = current.clamp(*min, *max) - *min;
+                let phase = if *direction < 0.0 {
+                    2.0 * range - along
+                } else {
+                    along
+                };
+                let phase = (phase + offset.abs()).rem_euclid(2.0 * range);
+                if phase < range {
+                    *direction = 1.0;
+                    *min + phase
+                } else {
+                    *direction = -1.0;
+                    *max - (phase - range)
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Bounds(Position, Size);
+
+    impl Rect for Bounds {
+        fn position(&self) -> Position {
+            self.0
+        }
+
+        fn position_mut(&mut self) -> &mut Position {
+            &mut self.0
+        }
+
+        fn size(&self) -> Size {
+            self.1
+        }
+    }
+
+    fn rect(x: Distance, y: Distance, width: Distance, height: Distance) -> Bounds {
+        Bounds(Position::new(x, y), Size::new(width, height))
+    }
+
+    #[test]
+    fn overlapping_rects_intersect() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert!(a.intersects(&rect(5.0, 5.0, 10.0, 10.0)));
+        assert!(a.intersects(&rect(-5.0, 2.0, 10.0, 2.0)));
+        // One inside the other
+        assert!(a.intersects(&rect(2.0, 2.0, 2.0, 2.0)));
+        assert!(rect(2.0, 2.0, 2.0, 2.0).intersects(&a));
+    }
+
+    #[test]
+    fn rects_sharing_an_edge_dont_intersect() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert!(!a.intersects(&rect(10.0, 0.0, 10.0, 10.0)));
+        assert!(!a.intersects(&rect(0.0, 10.0, 10.0, 10.0)));
+        assert!(!a.intersects(&rect(-10.0, 0.0, 10.0, 10.0)));
+        assert!(!a.intersects(&rect(10.0, 10.0, 5.0, 5.0)));
+    }
+
+    #[test]
+    fn disjoint_rects_dont_intersect() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert!(!a.intersects(&rect(20.0, 0.0, 10.0, 10.0)));
+        // Overlapping along x but not along y
+        assert!(!a.intersects(&rect(5.0, 20.0, 10.0, 10.0)));
+    }
+
+    #[test]
+    fn points_on_the_top_and_left_edges_are_inside() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert!(a.contains_point(0.0, 0.0));
+        assert!(a.contains_point(5.0, 0.0));
+        assert!(a.contains_point(0.0, 5.0));
+        assert!(a.contains_point(5.0, 5.0));
+    }
+
+    #[test]
+    fn points_on_the_bottom_and_right_edges_are_outside() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert!(!a.contains_point(10.0, 5.0));
+        assert!(!a.contains_point(5.0, 10.0));
+        assert!(!a.contains_point(10.0, 10.0));
+        assert!(!a.contains_point(-0.5, 5.0));
+        // So a point on a shared edge is in exactly one of the two rects
+        assert!(rect(10.0, 0.0, 10.0, 10.0).contains_point(10.0, 5.0));
+    }
+
+    #[test]
+    fn union_encloses_both_rects() {
+        let (origin, size) = rect(0.0, 0.0, 10.0, 10.0).union(&rect(5.0, -5.0, 20.0, 10.0));
+        assert_eq!((origin.x(), origin.y()), (0.0, -5.0));
+        assert_eq!((size.x(), size.y()), (25.0, 15.0));
+    }
+
+    #[test]
+    fn union_spans_the_gap_between_disjoint_rects() {
+        let (origin, size) = rect(20.0, 20.0, 5.0, 5.0).union(&rect(0.0, 0.0, 5.0, 5.0));
+        assert_eq!((origin.x(), origin.y()), (0.0, 0.0));
+        assert_eq!((size.x(), size.y()), (25.0, 25.0));
+    }
+
+    #[test]
+    fn union_with_an_enclosed_rect_is_the_outer_one() {
+        let (origin, size) = rect(0.0, 0.0, 10.0, 10.0).union(&rect(2.0, 3.0, 4.0, 4.0));
+        assert_eq!((origin.x(), origin.y()), (0.0, 0.0));
+        assert_eq!((size.x(), size.y()), (10.0, 10.0));
+    }
+
+    #[test]
+    fn intersection_area_is_the_overlap() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.intersection_area(&rect(5.0, 5.0, 10.0, 10.0)), 25.0);
+        assert_eq!(a.intersection_area(&rect(2.0, -5.0, 3.0, 20.0)), 30.0);
+        assert_eq!(a.intersection_area(&a), 100.0);
+    }
+
+    #[test]
+    fn intersection_area_is_zero_without_overlap() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.intersection_area(&rect(10.0, 0.0, 10.0, 10.0)), 0.0);
+        // Apart on both axes, where the negative width and height mustn't multiply out positive
+        assert_eq!(a.intersection_area(&rect(20.0, 20.0, 5.0, 5.0)), 0.0);
+    }
+
+    #[test]
+    fn length_of_pythagorean_triples() {
+        assert_eq!(Position::new(3.0, 4.0).length(), 5.0);
+        assert_eq!(Position::new(-5.0, 12.0).length(), 13.0);
+        assert_eq!(Size::new(8.0, -15.0).length(), 17.0);
+        assert_eq!(Vec2::new(-3.0, -4.0).length(), 5.0);
+    }
+
+    #[test]
+    fn zero_vectors_have_no_length() {
+        assert_eq!(Position::default().length(), 0.0);
+        assert_eq!(Vec2::default().length(), 0.0);
+        let here = Position::new(7.0, -2.0);
+        assert_eq!(here.distance_to(&here), 0.0);
+    }
+
+    #[test]
+    fn distance_to_is_the_same_both_ways() {
+        let a = Position::new(1.0, 2.0);
+        let b = Position::new(4.0, 6.0);
+        assert_eq!(a.distance_to(&b), 5.0);
+        assert_eq!(b.distance_to(&a), 5.0);
+        assert_eq!(
+            Position::new(-6.0, 0.0).distance_to(&Position::new(0.0, 8.0)),
+            10.0
+        );
+    }
+
+    #[test]
+    fn add_moves_past_offset_strategies() {
+        let mut position = Position::new(1.0, 2.0);
+        position.set_offset_xy(OffsetStrategy::limit(0.0, 5.0));
+        position.add(10.0, -3.0);
+        assert_eq!((position.x(), position.y()), (11.0, -1.0));
+        position.add(0.0, 0.0);
+        assert_eq!((position.x(), position.y()), (11.0, -1.0));
+    }
+
+    #[test]
+    fn wrap_within_range_moves_as_is() {
+        let mut wrap = OffsetStrategy::wrap(10.0, 20.0);
+        assert_eq!(wrap.offset(12.0, 3.0), 15.0);
+        assert_eq!(wrap.offset(15.0, -5.0), 10.0);
+    }
+
+    #[test]
+    fn wrap_past_max_comes_back_in_at_min() {
+        let mut wrap = OffsetStrategy::wrap(10.0, 20.0);
+        assert_eq!(wrap.offset(18.0, 2.0), 10.0);
+        assert_eq!(wrap.offset(18.0, 5.0), 13.0);
+        // Several ranges past the end land where a single lap would
+        assert_eq!(wrap.offset(18.0, 35.0), 13.0);
+        assert_eq!(wrap.offset(10.0, 1000.0), 10.0);
+    }
+
+    #[test]
+    fn wrap_past_min_comes_back_in_at_max() {
+        let mut wrap = OffsetStrategy::wrap(10.0, 20.0);
+        assert_eq!(wrap.offset(12.0, -5.0), 17.0);
+        assert_eq!(wrap.offset(12.0, -35.0), 17.0);
+        assert_eq!(wrap.offset(10.0, -1000.0), 10.0);
+        // A sliver below min stays inside rather than rounding up to max
+        let wrapped = wrap.offset(10.0, -1e-20);
+        assert!((10.0..20.0).contains(&wrapped), "{}", wrapped);
+    }
+
+    #[test]
+    fn wrap_stays_in_range() {
+        let mut wrap = OffsetStrategy::wrap(-3.0, 4.0);
+        let mut x = 0.0;
+        for step in [2.5, -11.0, 70.25, -0.75, 6.999, -700.1] {
+            x = wrap.offset(x, step);
+            assert!((-3.0..4.0).contains(&x), "{} after {}", x, step);
+        }
+    }
+
+    #[test]
+    fn wrap_with_an_empty_range_stays_at_min() {
+        assert_eq!(OffsetStrategy::wrap(5.0, 5.0).offset(3.0, 1.0), 5.0);
+        assert_eq!(OffsetStrategy::wrap(5.0, 2.0).offset(3.0, 1.0), 5.0);
+    }
+
+    #[test]
+    fn cycle_bounces_off_max() {
+        let mut cycle = OffsetStrategy::cycle(0.0, 10.0);
+        assert_eq!(cycle.offset(8.0, 5.0), 7.0);
+        assert!(matches!(cycle, OffsetStrategy::Cycle { direction, .. } if direction < 0.0));
+        // Heading back down, a further offset keeps going that way
+        assert_eq!(cycle.offset(7.0, 3.0), 4.0);
+    }
+
+    #[test]
+    fn cycle_bounces_several_times_in_one_offset() {
+        let mut cycle = OffsetStrategy::cycle(0.0, 10.0);
+        // Up 8 to max, down 10 to min, up 10 to max, and back 3
+        assert_eq!(cycle.offset(2.0, 31.0), 7.0);
+        assert!(matches!(cycle, OffsetStrategy::Cycle { direction, .. } if direction < 0.0));
+        // Two full laps end where they started, going the same way
+        assert_eq!(cycle.offset(7.0, 40.0), 7.0);
+        assert!(matches!(cycle, OffsetStrategy::Cycle { direction, .. } if direction < 0.0));
+    }
+
+    #[test]
+    fn cycle_matches_many_small_steps() {
+        let mut big = OffsetStrategy::cycle_with_direction(-5.0, 5.0, -1.0);
+        let mut small = big;
+        let jumped = big.offset(1.0, 47.0);
+        let mut stepped = 1.0;
+        for _ in 0..47 {
+            stepped = small.offset(stepped, 1.0);
+        }
+        assert_eq!(jumped, stepped);
+        assert_eq!(big, small);
+    }
+
+    #[test]
+    fn cycle_with_an_empty_range_stays_at_min() {
+        assert_eq!(OffsetStrategy::cycle(5.0, 5.0).offset(3.0, 1.0), 5.0);
+        assert_eq!(OffsetStrategy::cycle(5.0, 2.0).offset(3.0, 1.0), 5.0);
+    }
+
+    #[test]
+    fn ease_in_out_speeds_up_toward_the_offset() {
+        let mut ease = OffsetStrategy::ease_in_out(0.0, 1000.0, 0.5);
+        let mut x = 0.0;
+        let mut last_step = 0.0;
+        for _ in 0..10 {
+            let next = ease.offset(x, 4.0);
+            let step = next - x;
+            assert!(
+                step > last_step && step <= 4.0,
+                "{} after {}",
+                step,
+                last_step
+            );
+            (x, last_step) = (next, step);
+        }
+    }
+
+    #[test]
+    fn ease_in_out_converges_on_a_steady_offset() {
+        let mut ease = OffsetStrategy::ease_in_out(0.0, 1000.0, 0.25);
+        let mut x = 0.0;
+        for _ in 0..100 {
+            x = ease.offset(x, 2.0);
+        }
+        let next = ease.offset(x, 2.0);
+        assert!((next - x - 2.0).abs() < 1e-9, "{}", next - x);
+    }
+
+    #[test]
+    fn ease_in_out_coasts_to_a_stop() {
+        let mut ease = OffsetStrategy::ease_in_out(0.0, 1000.0, 0.5);
+        let mut x = 0.0;
+        for _ in 0..20 {
+            x = ease.offset(x, 4.0);
+        }
+        let mut last_step = 4.0;
+        for _ in 0..60 {
+            let next = ease.offset(x, 0.0);
+            let step = next - x;
+            assert!(
+                step >= 0.0 && step <= last_step,
+                "{} after {}",
+                step,
+                last_step
+            );
+            (x, last_step) = (next, step);
+        }
+        assert!(last_step < 1e-9, "{}", last_step);
+    }
+
+    #[test]
+    fn ease_in_out_stops_at_its_bounds() {
+        let mut ease = OffsetStrategy::ease_in_out(0.0, 10.0, 1.0);
+        assert_eq!(ease.offset(8.0, 5.0), 10.0);
+        assert!(matches!(ease, OffsetStrategy::EaseInOut { velocity, .. } if velocity == 0.0));
+    }
+
+    #[test]
+    fn oscillate_stays_within_its_amplitude() {
+        let mut oscillate = OffsetStrategy::oscillate(50.0, 8.0, 1000.0);
+        let mut y = 50.0;
+        let (mut lowest, mut highest) = (y, y);
+        for step in [16.0, 33.3, 7.0, 250.0, 1234.5, 16.0, 499.9].repeat(20) {
+            y = oscillate.offset(y, step);
+            assert!((42.0..=58.0).contains(&y), "{} after {}", y, step);
+            (lowest, highest) = (lowest.min(y), highest.max(y));
+        }
+        // And it actually swings, rather than staying put in the middle
+        assert!(lowest < 45.0 && highest > 55.0, "{}..{}", lowest, highest);
+    }
+
+    #[test]
+    fn oscillate_peaks_a_quarter_period_in() {
+        let mut oscillate = OffsetStrategy::oscillate(0.0, 3.0, 400.0);
+        assert!((oscillate.offset(0.0, 100.0) - 3.0).abs() < 1e-9);
+        assert!(oscillate.offset(0.0, 100.0).abs() < 1e-9);
+        assert!((oscillate.offset(0.0, 100.0) + 3.0).abs() < 1e-9);
+        // A whole period later it's back in the same place, whatever it was given
+        assert!((oscillate.offset(123.0, 400.0) + 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn oscillate_with_no_period_stays_in_the_middle() {
+        let mut oscillate = OffsetStrategy::oscillate(5.0, 3.0, 0.0);
+        assert_eq!(oscillate.offset(7.0, 100.0), 5.0);
+    }
+}
@@ -1,7 +1,557 @@
-use web_sys::CanvasRenderingContext2d;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+};
+
+use wasm_bindgen::{Clamped, JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::game::Input;
+use crate::geom::{Distance, Position, Rect, Size, XY};
+use crate::rng::Rng;
 
 pub type TimeStamp = f64;
 
+/// Game time as of the frame being drawn, as last given to [`set_now`]. Effects timed against
+/// it, like an entity's flash, hold still while the game does.
+pub fn now() -> TimeStamp {
+    NOW.with(Cell::get)
+}
+
+/// Set the game time that the next frame is drawn at, see [`now`]
+pub fn set_now(ts: TimeStamp) {
+    NOW.with(|now| now.set(ts));
+}
+
 pub trait Draw {
     fn draw(&mut self, context: &CanvasRenderingContext2d);
 }
+
+impl<T: Draw + ?Sized> Draw for Box<T> {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        (**self).draw(context);
+    }
+}
+
+/// Per-frame state handed to every [`GameObject`] update
+pub struct UpdateCtx<'a> {
+    /// Timestamp of this frame, in real time
+    pub ts: TimeStamp,
+    /// Game time elapsed since the last frame
+    pub offset_ts: TimeStamp,
+    /// The steering controls held down this frame
+    pub input: &'a Input,
+    /// Size of the play area
+    pub bounds: Size,
+}
+
+/// Something that's advanced and drawn once per frame, and that can die along the way, like a
+/// bullet leaving the screen
+///
+/// Game objects can be kept together as a `Vec<Box<dyn GameObject>>`, and [`update_all`] and
+/// [`draw_all`] run the update and cull, and draw steps for a whole collection of them.
+pub trait GameObject: Draw {
+    /// Advance by one frame, returning whether the object is still alive
+    fn update(&mut self, ctx: &UpdateCtx) -> bool;
+}
+
+impl<T: GameObject + ?Sized> GameObject for Box<T> {
+    fn update(&mut self, ctx: &UpdateCtx) -> bool {
+        (**self).update(ctx)
+    }
+}
+
+/// Update every object in `objects`, and drop the ones that died
+pub fn update_all<T: GameObject>(objects: &mut Vec<T>, ctx: &UpdateCtx) {
+    objects.retain_mut(|object| object.update(ctx));
+}
+
+/// Draw every object in `objects`
+pub fn draw_all<T: Draw>(objects: &mut [T], context: &CanvasRenderingContext2d) {
+    for object in objects.iter_mut() {
+        object.draw(context);
+    }
+}
+
+/// Format a span of milliseconds as `mm:ss`, with minutes running past 59 rather than wrapping
+pub fn format_time(ms: TimeStamp) -> String {
+    let seconds = (ms.max(0.0) / 1000.0) as u64;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+thread_local! {
+    static SPRITE_CACHE: RefCell<SpriteCache> = RefCell::new(SpriteCache::new());
+    static SUBPIXEL: Cell<bool> = const { Cell::new(false) };
+    static BLEND: Cell<bool> = const { Cell::new(false) };
+    static ZOOM: Cell<u32> = const { Cell::new(1) };
+    static HIGH_CONTRAST: Cell<bool> = const { Cell::new(false) };
+    static OUTLINE: Cell<bool> = const { Cell::new(false) };
+    static MEASURING: Cell<bool> = const { Cell::new(false) };
+    static DRAWN: RefCell<Vec<DrawnRect>> = const { RefCell::new(Vec::new()) };
+    static NOW: Cell<TimeStamp> = const { Cell::new(0.0) };
+}
+
+/// Render sprites at fractional positions instead of snapping them to whole pixels
+///
+/// `put_image_data` truncates its destination to integer pixels, so slow movement (like the fleet
+/// creeping along) visibly steps from pixel to pixel. With sub-pixel rendering on, sprites are
+/// drawn with `draw_image` instead, and image smoothing is turned off on `context` so that the
+/// pixels stay crisp rather than being blurred across their neighbors.
+pub fn set_subpixel(context: &CanvasRenderingContext2d, enabled: bool) {
+    SUBPIXEL.with(|subpixel| subpixel.set(enabled));
+    context.set_image_smoothing_enabled(!enabled);
+}
+
+/// Whether sub-pixel rendering is on, see [`set_subpixel`]
+pub fn subpixel() -> bool {
+    SUBPIXEL.with(Cell::get)
+}
+
+/// Blend sprites over whatever is already drawn beneath them
+///
+/// `put_image_data` replaces the pixels it covers outright, alpha included, so a sprite's
+/// transparent corners punch holes through anything underneath. That's fine over a cleared canvas,
+/// but once a background or starfield is painted first, sprites have to be composited with
+/// `draw_image` instead.
+pub fn set_blend(enabled: bool) {
+    BLEND.with(|blend| blend.set(enabled));
+}
+
+/// Canvas pixels per CSS pixel on this display, rounded to a whole number to use as a zoom
+///
+/// Sizing a canvas's backing store by `devicePixelRatio` keeps it sharp on high-DPI screens, but
+/// the sprites are pixel art: scaling them by a fractional ratio like 1.5 would smear their edges
+/// whichever way it's done, so the ratio is rounded and the play area takes up the difference.
+pub fn device_pixel_zoom() -> u32 {
+    let ratio = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+    ratio.round().max(1.0) as u32
+}
+
+/// Whether sprites are blended over the background, see [`set_blend`]
+pub fn blend() -> bool {
+    BLEND.with(Cell::get)
+}
+
+/// Draw everything `zoom` times larger, in whole canvas pixels per game pixel
+///
+/// The game keeps working in its own coordinates, a `zoom`th of the canvas's size, and `context`
+/// is given a matching scale transform, so bounds and collisions are the same at every zoom.
+/// `put_image_data` ignores transforms, so while zoomed in sprites go through `draw_image`, with
+/// image smoothing off to keep the enlarged pixels square. Call this after [`set_subpixel`], which
+/// also sets image smoothing.
+pub fn set_zoom(context: &CanvasRenderingContext2d, zoom: u32) {
+    let zoom = zoom.max(1);
+    ZOOM.with(|cell| cell.set(zoom));
+    let scale = f64::from(zoom);
+    context
+        .set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0)
+        .expect("set_transform");
+    if zoom > 1 {
+        context.set_image_smoothing_enabled(false);
+    }
+}
+
+/// The current zoom factor, see [`set_zoom`]
+pub fn zoom() -> u32 {
+    ZOOM.with(Cell::get)
+}
+
+/// Recolor sprites into a high-contrast palette that stays distinguishable with color blindness
+///
+/// Unlike the other rendering options this is applied to sprites' pixels when they're created,
+/// so it has to be set before a game builds its entities, and kept set while it spawns more.
+pub fn set_high_contrast(enabled: bool) {
+    HIGH_CONTRAST.with(|high_contrast| high_contrast.set(enabled));
+}
+
+/// Whether new sprites are recolored, see [`set_high_contrast`]
+pub fn high_contrast() -> bool {
+    HIGH_CONTRAST.with(Cell::get)
+}
+
+/// Trace a 1px outline around every sprite, just inside its bounds so neighbors' outlines never
+/// run into each other
+pub fn set_outline(enabled: bool) {
+    OUTLINE.with(|outline| outline.set(enabled));
+}
+
+/// Whether sprites are outlined, see [`set_outline`]
+pub fn outline() -> bool {
+    OUTLINE.with(Cell::get)
+}
+
+/// Look up (or render and cache) the offscreen canvas for an RGBA sprite, see [`SpriteCache`]
+pub fn sprite(width: u32, height: u32, data: &[u8]) -> Result<HtmlCanvasElement, JsValue> {
+    SPRITE_CACHE.with(|cache| cache.borrow_mut().get(width, height, data))
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SpriteKey {
+    width: u32,
+    height: u32,
+    data: Rc<[u8]>,
+}
+
+/// Offscreen canvases holding pre-rendered sprites, usable as a source for `draw_image`
+///
+/// Sprites are keyed by their dimensions and pixel contents, so every entity showing the same
+/// image shares one canvas, and an entity whose pixels have been changed gets a new one. There
+/// are only a handful of distinct sprites in play at once, so the cache is capped at
+/// [`SpriteCache::CAPACITY`] entries and evicts the oldest first. Evicting a sprite never breaks
+/// an entity that is already holding its canvas.
+pub struct SpriteCache {
+    sprites: HashMap<SpriteKey, HtmlCanvasElement>,
+    order: VecDeque<SpriteKey>,
+}
+
+impl SpriteCache {
+    pub const CAPACITY: usize = 64;
+
+    pub fn new() -> Self {
+        Self {
+            sprites: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<HtmlCanvasElement, JsValue> {
+        let key = SpriteKey {
+            width,
+            height,
+            data: data.into(),
+        };
+        if let Some(canvas) = self.sprites.get(&key) {
+            return Ok(canvas.clone());
+        }
+
+        let canvas = render_sprite(width, height, data)?;
+        if self.order.len() >= Self::CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.sprites.remove(&oldest);
+            }
+        }
+        self.order.push_back(key.clone());
+        self.sprites.insert(key, canvas.clone());
+        Ok(canvas)
+    }
+}
+
+impl Default for SpriteCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn render_sprite(width: u32, height: u32, data: &[u8]) -> Result<HtmlCanvasElement, JsValue> {
+    let (canvas, context) = offscreen_canvas(width, height)?;
+    let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(data), width, height)?;
+    context.put_image_data(&image, 0.0, 0.0)?;
+    Ok(canvas)
+}
+
+/// A new canvas that isn't part of the page, and its 2d context
+fn offscreen_canvas(
+    width: u32,
+    height: u32,
+) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| JsValue::from_str("no document to create an offscreen canvas in"))?;
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let context = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("offscreen canvas has no 2d context"))?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    Ok((canvas, context))
+}
+
+/// An offscreen canvas that a whole frame is drawn into before being copied onto the page at once
+///
+/// Drawing a sprite with `put_image_data` copies its pixels out of wasm into a new `ImageData`
+/// every time. Composited in a backbuffer instead, every sprite is drawn from its cached canvas
+/// (see [`sprite`]) with `draw_image`, so no pixel data crosses over per frame, and the page's
+/// canvas is only touched once, by [`present`](Self::present).
+pub struct Backbuffer {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+}
+
+impl Backbuffer {
+    pub fn new(width: u32, height: u32) -> Result<Self, JsValue> {
+        let (canvas, context) = offscreen_canvas(width, height)?;
+        Ok(Self { canvas, context })
+    }
+
+    /// The context to draw the frame with. Sprites have to be [blended](set_blend), as
+    /// `put_image_data` would skip the backbuffer's transform.
+    pub fn context(&self) -> &CanvasRenderingContext2d {
+        &self.context
+    }
+
+    /// Match the size of the page's canvas, which clears the backbuffer if it changes. Returns
+    /// whether it did.
+    pub fn resize(&self, width: u32, height: u32) -> bool {
+        if (self.canvas.width(), self.canvas.height()) == (width, height) {
+            return false;
+        }
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+        true
+    }
+
+    /// Replace everything on `context`'s canvas with the finished frame
+    pub fn present(&self, context: &CanvasRenderingContext2d) {
+        let (width, height) = (
+            f64::from(self.canvas.width()),
+            f64::from(self.canvas.height()),
+        );
+        context.save();
+        context
+            .set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+            .expect("set_transform");
+        // The frame can have transparent parts, which mustn't show the last frame through them
+        context.clear_rect(0.0, 0.0, width, height);
+        context
+            .draw_image_with_html_canvas_element(&self.canvas, 0.0, 0.0)
+            .expect("draw_image");
+        context.restore();
+    }
+}
+
+/// The box something was drawn in during a frame, along with a fingerprint of how it looked, so
+/// that two frames can be compared to find what changed between them; see [`measure`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawnRect {
+    pub x: Distance,
+    pub y: Distance,
+    pub width: Distance,
+    pub height: Distance,
+    /// Anything besides position that changes what's drawn, like opacity or rotation, hashed
+    pub look: u64,
+}
+
+impl DrawnRect {
+    /// Its fields' bits, to compare or hash rects exactly
+    fn key(&self) -> [u64; 5] {
+        [
+            self.x.to_bits(),
+            self.y.to_bits(),
+            self.width.to_bits(),
+            self.height.to_bits(),
+            self.look,
+        ]
+    }
+}
+
+/// Run `draw` with drawing turned off, collecting where everything it would have drawn would go
+///
+/// Sprites and other objects check [`measuring`] before touching the context, and report their
+/// boxes with [`record`] instead. Measuring a frame before drawing it lets a game work out which
+/// parts of the screen need redrawing with [`changed_rects`].
+pub fn measure(draw: impl FnOnce()) -> Vec<DrawnRect> {
+    DRAWN.with(|drawn| drawn.borrow_mut().clear());
+    MEASURING.with(|measuring| measuring.set(true));
+    draw();
+    MEASURING.with(|measuring| measuring.set(false));
+    DRAWN.with(|drawn| std::mem::take(&mut *drawn.borrow_mut()))
+}
+
+/// Whether a [`measure`] is running, in which case nothing should be drawn
+pub fn measuring() -> bool {
+    MEASURING.with(Cell::get)
+}
+
+/// Note where something would be drawn, during a [`measure`]
+pub fn record(rect: DrawnRect) {
+    DRAWN.with(|drawn| drawn.borrow_mut().push(rect));
+}
+
+/// Everything that differs between a frame that drew `last` and one that draws `next`: whatever
+/// was drawn in one and not, in the same place and the same way, in the other
+///
+/// Something that moved shows up twice, once where it was, to be cleared, and once where it is.
+pub fn changed_rects(last: &[DrawnRect], next: &[DrawnRect]) -> Vec<DrawnRect> {
+    let mut counts: HashMap<[u64; 5], (DrawnRect, isize)> = HashMap::new();
+    for (rects, delta) in [(last, 1), (next, -1)] {
+        for rect in rects {
+            counts.entry(rect.key()).or_insert((*rect, 0)).1 += delta;
+        }
+    }
+    counts
+        .into_values()
+        .filter(|(_, count)| *count != 0)
+        .map(|(rect, _)| rect)
+        .collect()
+}
+
+/// A box on the canvas to redraw, see [`merge_overlapping`]
+struct Region(Position, Size);
+
+impl Rect for Region {
+    fn position(&self) -> Position {
+        self.0
+    }
+
+    fn position_mut(&mut self) -> &mut Position {
+        &mut self.0
+    }
+
+    fn size(&self) -> Size {
+        self.1
+    }
+}
+
+/// The boxes covering `rects`, with any that overlap merged into the smallest box enclosing
+/// them, so something that moved a little is redrawn as one box rather than two
+pub fn merge_overlapping(rects: &[DrawnRect]) -> Vec<(Position, Size)> {
+    let mut merged: Vec<Region> = Vec::new();
+    for rect in rects {
+        let mut region = Region(
+            Position::new(rect.x, rect.y),
+            Size::new(rect.width, rect.height),
+        );
+        // A merged box can reach others that neither half did, so keep going until none overlap
+        while let Some(i) = merged
+            .iter()
+            .position(|other| region.intersection_area(other) > 0.0)
+        {
+            let (position, size) = region.union(&merged.swap_remove(i));
+            region = Region(position, size);
+        }
+        merged.push(region);
+    }
+    merged
+        .into_iter()
+        .map(|Region(position, size)| (position, size))
+        .collect()
+}
+
+/// A field of background stars that drift down the screen, wrapping back to the top
+///
+/// Stars are split into a few layers of brightness, with dimmer (more distant) stars moving more
+/// slowly for a parallax effect. Each layer is drawn with a single fill style, so the per-frame
+/// cost is one `fill_rect` per star.
+pub struct Starfield {
+    layers: Vec<Vec<(Distance, Distance)>>,
+    bounds: Size,
+    /// Speed of the brightest, nearest stars; each dimmer layer moves proportionally slower
+    pub rate: f64,
+}
+
+impl Starfield {
+    const LAYERS: usize = 4;
+    const STAR_SIZE: Distance = 2.0;
+
+    pub fn new(count: usize, bounds: Size, rng: &mut Rng) -> Self {
+        let mut layers = vec![Vec::new(); Self::LAYERS];
+        for _ in 0..count {
+            let layer = (rng.next_f64() * Self::LAYERS as f64) as usize;
+            let star = (rng.range(0.0, bounds.x()), rng.range(0.0, bounds.y()));
+            layers[layer.min(Self::LAYERS - 1)].push(star);
+        }
+
+        Self {
+            layers,
+            bounds,
+            rate: 0.05,
+        }
+    }
+
+    /// Spread the stars out (or squeeze them in) to cover a play area that's changed size
+    pub fn resize(&mut self, bounds: Size) {
+        let scale_x = bounds.x() / self.bounds.x();
+        let scale_y = bounds.y() / self.bounds.y();
+        for (x, y) in self.layers.iter_mut().flatten() {
+            *x *= scale_x;
+            *y *= scale_y;
+        }
+        self.bounds = bounds;
+    }
+
+    pub fn update(&mut self, offset_ts: TimeStamp) {
+        let height = self.bounds.y();
+        for (layer_idx, layer) in self.layers.iter_mut().enumerate() {
+            let offset = offset_ts * self.rate * Self::depth(layer_idx);
+            for (_, y) in layer.iter_mut() {
+                *y = (*y + offset).rem_euclid(height);
+            }
+        }
+    }
+
+    /// How near the stars in a layer are, from the dimmest and slowest layer up to `1.0`
+    fn depth(layer_idx: usize) -> f64 {
+        (layer_idx + 1) as f64 / Self::LAYERS as f64
+    }
+}
+
+impl Draw for Starfield {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        for (layer_idx, layer) in self.layers.iter().enumerate() {
+            let depth = Self::depth(layer_idx);
+            context.set_fill_style_str(&format!("rgba(255, 255, 255, {})", depth));
+            for (x, y) in layer {
+                context.fill_rect(*x, *y, Self::STAR_SIZE, Self::STAR_SIZE);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drawn(x: Distance, y: Distance, width: Distance, height: Distance) -> DrawnRect {
+        DrawnRect {
+            x,
+            y,
+            width,
+            height,
+            look: 0,
+        }
+    }
+
+    fn boxes(rects: &[DrawnRect]) -> Vec<[Distance; 4]> {
+        let mut boxes: Vec<_> = merge_overlapping(rects)
+            .into_iter()
+            .map(|(position, size)| [position.x(), position.y(), size.x(), size.y()])
+            .collect();
+        boxes.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        boxes
+    }
+
+    #[test]
+    fn overlapping_rects_merge_into_one_box() {
+        // A sprite that moved 4 to the right, and one elsewhere that didn't touch it
+        let rects = [
+            drawn(10.0, 10.0, 16.0, 16.0),
+            drawn(100.0, 10.0, 16.0, 16.0),
+            drawn(14.0, 10.0, 16.0, 16.0),
+        ];
+        assert_eq!(
+            boxes(&rects),
+            vec![[10.0, 10.0, 20.0, 16.0], [100.0, 10.0, 16.0, 16.0]]
+        );
+    }
+
+    #[test]
+    fn merging_continues_through_chains_of_rects() {
+        // The last rect bridges the first two, which only share an edge, into one box
+        let rects = [
+            drawn(0.0, 0.0, 10.0, 10.0),
+            drawn(10.0, 0.0, 10.0, 10.0),
+            drawn(5.0, 5.0, 10.0, 10.0),
+        ];
+        assert_eq!(boxes(&rects), vec![[0.0, 0.0, 20.0, 15.0]]);
+        assert_eq!(boxes(&rects[..2]).len(), 2);
+    }
+}
@@ -0,0 +1,51 @@
+//! The best score so far, kept across visits in the browser's `localStorage`
+//!
+//! Storage can be missing or refuse access (in private browsing, or with cookies blocked), in
+//! which case the best score is only kept in memory, for as long as the page is open.
+
+use web_sys::{console, Storage};
+
+#[derive(Default)]
+pub(crate) struct HighScore {
+    best: u32,
+    storage: Option<Storage>,
+}
+
+impl HighScore {
+    const KEY: &'static str = "canvas-invaders.high-score";
+
+    /// The stored best score, or zero if nothing usable has been stored
+    pub(crate) fn load() -> Self {
+        let storage = web_sys::window().and_then(|window| window.local_storage().ok().flatten());
+        if storage.is_none() {
+            console::log_1(&"No localStorage, the high score won't be saved".into());
+        }
+        let best = storage
+            .as_ref()
+            .and_then(|storage| storage.get_item(Self::KEY).ok().flatten())
+            .map_or(0, |stored| parse(&stored));
+        Self { best, storage }
+    }
+
+    pub(crate) fn best(&self) -> u32 {
+        self.best
+    }
+
+    /// Keep `points` as the new best score if it beats the old one, saving it if possible
+    pub(crate) fn record(&mut self, points: u32) {
+        if points <= self.best {
+            return;
+        }
+        self.best = points;
+        if let Some(storage) = &self.storage {
+            if let Err(err) = storage.set_item(Self::KEY, &points.to_string()) {
+                console::log_1(&format!("Failed to save the high score: {:?}", err).into());
+            }
+        }
+    }
+}
+
+/// A stored score, or zero if it's been mangled into something that isn't one
+fn parse(stored: &str) -> u32 {
+    stored.trim().parse().unwrap_or(0)
+}
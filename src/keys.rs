@@ -0,0 +1,197 @@
+//! Which keyboard keys steer, fire, and pause, selected with the `data-keys` attribute on the
+//! game canvas (`wasd`, `arrows`, or `zx`)
+//!
+//! Keys are named by their `KeyboardEvent.key` values, so `"a"`, `"ArrowLeft"`, or `" "` for the
+//! space bar. The one-off toggles (`l` for the firing line, `f` for the frame rate, and Enter to
+//! restart) aren't rebindable.
+
+use std::str::FromStr;
+
+use crate::game::GameInput;
+
+/// The keys bound to each action. A key can be bound to more than one, and an action to any number
+/// of keys, including none.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyBindings {
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+    /// Steers up in free-move mode, and otherwise fires
+    pub up: Vec<String>,
+    /// Steers down in free-move mode, and otherwise does nothing
+    pub down: Vec<String>,
+    pub fire: Vec<String>,
+    pub pause: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    /// Both WASD and the arrow keys, with the space bar firing
+    fn default() -> Self {
+        Self {
+            left: keys(&["a", "ArrowLeft"]),
+            right: keys(&["d", "ArrowRight"]),
+            up: keys(&["w", "ArrowUp"]),
+            down: keys(&["s", "ArrowDown"]),
+            fire: keys(&[" ", "Spacebar"]),
+            pause: keys(&["p", "Escape"]),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// WASD to steer, leaving the arrow keys free
+    pub fn wasd() -> Self {
+        Self {
+            left: keys(&["a"]),
+            right: keys(&["d"]),
+            up: keys(&["w"]),
+            down: keys(&["s"]),
+            ..Self::default()
+        }
+    }
+
+    /// The arrow keys to steer, leaving the letters free
+    pub fn arrows() -> Self {
+        Self {
+            left: keys(&["ArrowLeft"]),
+            right: keys(&["ArrowRight"]),
+            up: keys(&["ArrowUp"]),
+            down: keys(&["ArrowDown"]),
+            ..Self::default()
+        }
+    }
+
+    /// The old home computer layout: Z and X steer left and right, and ' and / up and down
+    pub fn zx() -> Self {
+        Self {
+            left: keys(&["z"]),
+            right: keys(&["x"]),
+            up: keys(&["'"]),
+            down: keys(&["/"]),
+            ..Self::default()
+        }
+    }
+
+    /// The input a key press (or release, if `pressed` is false) stands for, if any. Firing,
+    /// pausing, and the toggles only happen as a key goes down.
+    pub fn input(&self, key: &str, pressed: bool, free_move: bool) -> Option<GameInput> {
+        let bound = |keys: &[String]| keys.iter().any(|bound| bound == key);
+        if bound(&self.left) {
+            Some(GameInput::Left(pressed))
+        } else if bound(&self.right) {
+            Some(GameInput::Right(pressed))
+        } else if free_move && bound(&self.up) {
+            Some(GameInput::Up(pressed))
+        } else if free_move && bound(&self.down) {
+            Some(GameInput::Down(pressed))
+        } else if !pressed {
+            None
+        } else if bound(&self.pause) {
+            Some(GameInput::TogglePause)
+        } else if bound(&self.fire) || bound(&self.up) {
+            Some(GameInput::Fire)
+        } else {
+            match key {
+                "l" => Some(GameInput::ToggleFiringLine),
+                "f" => Some(GameInput::ToggleFps),
+                "Enter" => Some(GameInput::Restart),
+                _ => None,
+            }
+        }
+    }
+}
+
+impl FromStr for KeyBindings {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "wasd" => Ok(Self::wasd()),
+            "arrows" => Ok(Self::arrows()),
+            "zx" => Ok(Self::zx()),
+            other => Err(format!("unknown key bindings \"{}\"", other)),
+        }
+    }
+}
+
+fn keys(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steering_keys_report_presses_and_releases() {
+        let keys = KeyBindings::default();
+        assert_eq!(keys.input("a", true, false), Some(GameInput::Left(true)));
+        assert_eq!(
+            keys.input("ArrowLeft", false, false),
+            Some(GameInput::Left(false))
+        );
+        assert_eq!(keys.input("d", true, false), Some(GameInput::Right(true)));
+        assert_eq!(
+            keys.input("ArrowRight", false, false),
+            Some(GameInput::Right(false))
+        );
+    }
+
+    #[test]
+    fn up_fires_unless_moving_freely() {
+        let keys = KeyBindings::default();
+        assert_eq!(keys.input("w", true, false), Some(GameInput::Fire));
+        assert_eq!(keys.input("w", false, false), None);
+        assert_eq!(keys.input("s", true, false), None);
+        assert_eq!(keys.input("w", true, true), Some(GameInput::Up(true)));
+        assert_eq!(
+            keys.input("ArrowDown", false, true),
+            Some(GameInput::Down(false))
+        );
+    }
+
+    #[test]
+    fn actions_happen_only_on_key_down() {
+        let keys = KeyBindings::default();
+        assert_eq!(keys.input(" ", true, false), Some(GameInput::Fire));
+        assert_eq!(keys.input(" ", false, false), None);
+        assert_eq!(
+            keys.input("Escape", true, false),
+            Some(GameInput::TogglePause)
+        );
+        assert_eq!(keys.input("p", false, false), None);
+        assert_eq!(keys.input("Enter", true, false), Some(GameInput::Restart));
+        assert_eq!(
+            keys.input("l", true, false),
+            Some(GameInput::ToggleFiringLine)
+        );
+        assert_eq!(keys.input("f", false, false), None);
+    }
+
+    #[test]
+    fn presets_leave_the_other_keys_unbound() {
+        let wasd = KeyBindings::wasd();
+        assert_eq!(wasd.input("ArrowLeft", true, false), None);
+        assert_eq!(wasd.input("a", true, false), Some(GameInput::Left(true)));
+
+        let arrows = KeyBindings::arrows();
+        assert_eq!(arrows.input("a", true, false), None);
+        assert_eq!(
+            arrows.input("ArrowUp", true, true),
+            Some(GameInput::Up(true))
+        );
+
+        let zx = KeyBindings::zx();
+        assert_eq!(zx.input("z", true, false), Some(GameInput::Left(true)));
+        assert_eq!(zx.input("x", true, false), Some(GameInput::Right(true)));
+        assert_eq!(zx.input("/", true, true), Some(GameInput::Down(true)));
+        assert_eq!(zx.input("a", true, false), None);
+    }
+
+    #[test]
+    fn presets_parse_by_name() {
+        assert_eq!("WASD".parse(), Ok(KeyBindings::wasd()));
+        assert_eq!("arrows".parse(), Ok(KeyBindings::arrows()));
+        assert_eq!("zx".parse(), Ok(KeyBindings::zx()));
+        assert!("dvorak".parse::<KeyBindings>().is_err());
+    }
+}
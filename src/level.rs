@@ -0,0 +1,218 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::entities::ENEMY_SPRITES;
+use crate::fire::FirePattern;
+use crate::geom::Distance;
+use crate::graphics::TimeStamp;
+
+/// A level's layout and tuning, loaded from JSON with [`load_level`]
+///
+/// ```json
+/// {
+///     "fleet": [
+///         [0, 0, 0, 0],
+///         [1, null, null, 1],
+///         [{ "sprite": 3, "health": 2 }, 2, 2, { "sprite": 3, "health": 2 }]
+///     ],
+///     "fleet_rate": 0.04,
+///     "start_left": true,
+///     "bunkers": [120, 300, 480],
+///     "points": [50, 40, 30, 20],
+///     "fire_pattern": { "kind": "sweep", "interval": 800 },
+///     "spawn": "drop_in",
+///     "background": "#001",
+///     "boss": { "health": 30, "points": 2000 }
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct LevelDef {
+    /// The fleet's formation, top row first; `null` cells are left empty
+    pub fleet: Vec<Vec<Option<CellDef>>>,
+    /// Horizontal fleet speed, overriding the difficulty setting
+    #[serde(default)]
+    pub fleet_rate: Option<f64>,
+    /// Whether the fleet starts out sweeping left, from the right edge, instead of right
+    #[serde(default)]
+    pub start_left: bool,
+    /// Time between enemy shots, overriding the difficulty setting
+    #[serde(default)]
+    pub enemy_fire_interval: Option<TimeStamp>,
+    /// Scripted enemy fire, replacing random shots every `enemy_fire_interval`
+    #[serde(default)]
+    pub fire_pattern: Option<FirePattern>,
+    /// How the fleet's members appear at the start of the level
+    #[serde(default)]
+    pub spawn: Option<SpawnStyle>,
+    /// Delay between neighboring members starting their spawn animation
+    #[serde(default)]
+    pub spawn_stagger: Option<TimeStamp>,
+    /// Horizontal positions of the middles of defensive bunkers, in place of any evenly spaced
+    /// ones
+    #[serde(default)]
+    pub bunkers: Vec<Distance>,
+    /// CSS color painted behind the level
+    #[serde(default)]
+    pub background: Option<String>,
+    /// Points for destroying each kind of enemy, indexed by sprite, replacing the defaults
+    #[serde(default)]
+    pub points: Option<Vec<u32>>,
+    /// A boss to fight once the fleet is cleared, before moving on to the next level
+    #[serde(default)]
+    pub boss: Option<BossDef>,
+}
+
+/// The tuning for a [`LevelDef`]'s boss, with anything left out taking the usual value
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BossDef {
+    /// Number of hits it takes to defeat
+    pub health: Option<u32>,
+    /// Size relative to a regular enemy
+    pub scale: Option<f64>,
+    /// Horizontal speed
+    pub rate: Option<f64>,
+    /// Average time between shots
+    pub fire_interval: Option<TimeStamp>,
+    /// Points for defeating it
+    pub points: Option<u32>,
+}
+
+/// One enemy in a [`LevelDef`] formation, either just its sprite index or an object giving the
+/// sprite and how many hits it takes
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum CellDef {
+    Sprite(usize),
+    Enemy {
+        sprite: usize,
+        #[serde(default = "CellDef::default_health")]
+        health: u32,
+    },
+}
+
+impl CellDef {
+    fn default_health() -> u32 {
+        1
+    }
+
+    pub fn sprite(&self) -> usize {
+        match self {
+            CellDef::Sprite(sprite) | CellDef::Enemy { sprite, .. } => *sprite,
+        }
+    }
+
+    pub fn health(&self) -> u32 {
+        match self {
+            CellDef::Sprite(_) => Self::default_health(),
+            CellDef::Enemy { health, .. } => *health,
+        }
+    }
+}
+
+/// How a fleet's members animate into their places before play starts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpawnStyle {
+    /// Members are in place from the start
+    #[default]
+    None,
+    /// Members fade in where they stand
+    Fade,
+    /// Members drop in from above the play area
+    DropIn,
+}
+
+impl FromStr for SpawnStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(SpawnStyle::None),
+            "fade" => Ok(SpawnStyle::Fade),
+            "drop_in" | "drop-in" => Ok(SpawnStyle::DropIn),
+            other => Err(format!("unknown spawn style \"{}\"", other)),
+        }
+    }
+}
+
+/// A sequence of levels played in order, loaded with [`load_campaign`]
+pub type Campaign = Vec<LevelDef>;
+
+#[derive(Debug)]
+pub enum LevelError {
+    Parse(serde_json::Error),
+    UnknownSprite {
+        row: usize,
+        column: usize,
+        sprite: usize,
+    },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Parse(err) => write!(f, "invalid level JSON: {}", err),
+            LevelError::UnknownSprite {
+                row,
+                column,
+                sprite,
+            } => write!(
+                f,
+                "enemy at row {}, column {} uses sprite {}, but only {} sprites exist",
+                row, column, sprite, ENEMY_SPRITES
+            ),
+        }
+    }
+}
+
+impl Error for LevelError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LevelError::Parse(err) => Some(err),
+            LevelError::UnknownSprite { .. } => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for LevelError {
+    fn from(err: serde_json::Error) -> Self {
+        LevelError::Parse(err)
+    }
+}
+
+impl LevelDef {
+    fn validate(&self) -> Result<(), LevelError> {
+        for (row, cells) in self.fleet.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                if let Some(sprite) = cell.as_ref().map(CellDef::sprite) {
+                    if sprite >= ENEMY_SPRITES {
+                        return Err(LevelError::UnknownSprite {
+                            row,
+                            column,
+                            sprite,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse and validate a single [`LevelDef`]
+pub fn load_level(json: &str) -> Result<LevelDef, LevelError> {
+    let level: LevelDef = serde_json::from_str(json)?;
+    level.validate()?;
+    Ok(level)
+}
+
+/// Parse and validate a JSON array of levels
+pub fn load_campaign(json: &str) -> Result<Campaign, LevelError> {
+    let campaign: Campaign = serde_json::from_str(json)?;
+    for level in &campaign {
+        level.validate()?;
+    }
+    Ok(campaign)
+}
@@ -1,21 +1,104 @@
+pub mod atlas;
+mod audio;
+#[cfg(feature = "benchmark")]
+mod bench;
+pub mod difficulty;
 mod entities;
+pub mod fire;
+mod fps;
+pub mod game;
+mod gamepad;
 mod geom;
 mod graphics;
+mod highscore;
+pub mod keys;
+pub mod level;
+mod png;
+mod replay;
+mod rng;
+mod saved;
+mod score;
 
-use std::{cell::RefCell, rc::Rc, sync::mpsc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::mpsc};
 
 use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::console;
 
-use crate::geom::Distance;
+use crate::game::{Game, GameInput};
+use crate::gamepad::GamepadPoller;
 use crate::graphics::TimeStamp;
 
-const MARGIN_X: Distance = 30.0;
-const MARGIN_Y: Distance = 48.0;
+thread_local! {
+    // Where `set_time_scale` sends a new time scale to the game on each canvas, by the canvas's id
+    static TIME_SCALES: RefCell<HashMap<String, mpsc::SyncSender<f64>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Scale the passage of game time in the game on the canvas with the id `canvas_id`, see
+/// [`Game::set_time_scale`]. The new scale takes effect from the game's next frame.
+#[wasm_bindgen]
+pub fn set_time_scale(canvas_id: &str, scale: f64) {
+    let sent = TIME_SCALES.with(|senders| {
+        senders
+            .borrow()
+            .get(canvas_id)
+            .is_some_and(|sender| sender.try_send(scale).is_ok())
+    });
+    if !sent {
+        console::log_1(&format!("No game on `{}` to set the time scale of", canvas_id).into());
+    }
+}
 
 #[wasm_bindgen(start)]
 pub fn start() {
-    let window = web_sys::window().unwrap();
+    // Pages with a single `#game` canvas get a game automatically; pages hosting several games
+    // call `start_game` for each of their canvases instead
+    let has_default_canvas = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id("game"))
+        .is_some();
+    if has_default_canvas {
+        start_game("game");
+    }
+}
+
+/// Start a game on the canvas with the id `canvas_id`
+///
+/// Every game keeps its own state and animation loop, so several can run side by side on one page.
+/// Keyboard input goes to whichever game's canvas has focus: each canvas is made focusable, the
+/// most recently started game takes focus, and clicking a canvas (or tabbing to it) hands the
+/// keyboard over to that game.
+///
+/// A page that can't host a game (no such canvas, or one the browser won't draw on) gets the
+/// reason logged to the console instead.
+#[wasm_bindgen]
+pub fn start_game(canvas_id: &str) {
+    if let Err(err) = try_start(canvas_id) {
+        console::error_1(&err);
+    }
+}
+
+/// Set up the game on `canvas_id` and kick off its animation loop, failing if the page isn't
+/// what it should be
+fn try_start(canvas_id: &str) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or("No global `window` exists")?;
+    let document = window.document().ok_or("The window has no document")?;
+    let canvas = document
+        .get_element_by_id(canvas_id)
+        .ok_or_else(|| format!("No element with the id `{}`", canvas_id))?
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .map_err(|_| format!("The element `{}` isn't a canvas", canvas_id))?;
+
+    // `data-fit` keeps the canvas's drawing area the size it's laid out at, so the page's CSS
+    // decides how big the game is. `data-hidpi` does the same at the display's full resolution,
+    // and the game zooms in to match.
+    let hidpi = canvas.has_attribute("data-hidpi");
+    let fit = hidpi || canvas.has_attribute("data-fit");
+    if fit {
+        fit_canvas(&canvas, hidpi);
+    }
+    let now = window.performance().ok_or("Timing isn't available")?.now();
+    let mut game = Game::new(&canvas, now)?;
 
     let (key_sender, key_receiver) = mpsc::sync_channel(100);
     let key_event_closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::KeyboardEvent| {
@@ -31,31 +114,103 @@ pub fn start() {
             console::log_1(&format!("Failed to send key event, {}: {}", err, evt.key()).into());
         }
     });
-    window
-        .add_event_listener_with_callback("keydown", key_event_closure.as_ref().unchecked_ref())
-        .unwrap();
-    window
-        .add_event_listener_with_callback("keyup", key_event_closure.as_ref().unchecked_ref())
-        .unwrap();
+    // Listen on the canvas rather than the window so keys only reach the focused game
+    if !canvas.has_attribute("tabindex") {
+        canvas.set_tab_index(0);
+    }
+    canvas
+        .add_event_listener_with_callback("keydown", key_event_closure.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("keyup", key_event_closure.as_ref().unchecked_ref())?;
     // Must std::mem::forget() the closure so JavaScript holds onto the memory for the lifetime of
     // the program
     key_event_closure.forget();
+    canvas.focus()?;
 
-    let document = window.document().unwrap();
-    let canvas = document.get_element_by_id("game").unwrap();
-    let canvas: web_sys::HtmlCanvasElement = canvas
-        .dyn_into::<web_sys::HtmlCanvasElement>()
-        .map_err(|_| ())
-        .unwrap();
-    let canvas_width = Distance::from(canvas.width());
-    let canvas_height = Distance::from(canvas.height());
-
-    let context = canvas
-        .get_context("2d")
-        .unwrap()
-        .unwrap()
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()
-        .unwrap();
+    // The mouse steers the ship alongside the keyboard: moving it puts the ship under the cursor,
+    // and pressing a button fires
+    let (mouse_sender, mouse_receiver) = mpsc::sync_channel(100);
+    let mouse_event_closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::MouseEvent| {
+        if let Err(err) = mouse_sender.try_send(event) {
+            console::log_1(&format!("Failed to send mouse event, {}", err).into());
+        }
+    });
+    canvas.add_event_listener_with_callback(
+        "mousemove",
+        mouse_event_closure.as_ref().unchecked_ref(),
+    )?;
+    canvas.add_event_listener_with_callback(
+        "mousedown",
+        mouse_event_closure.as_ref().unchecked_ref(),
+    )?;
+    mouse_event_closure.forget();
+    let pointer_canvas = canvas.clone();
+
+    // On touch screens, a finger put down on the left half of the canvas steers the ship toward
+    // it for as long as it stays down, wherever it's dragged, while a tap anywhere on the right
+    // half fires. Touches are told apart by their identifiers, so one finger can steer while
+    // another fires.
+    let (touch_sender, touch_receiver) = mpsc::sync_channel(100);
+    let touch_event_closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::TouchEvent| {
+        // Keep the browser from scrolling or zooming the page instead
+        event.prevent_default();
+        if let Err(err) = touch_sender.try_send(event) {
+            console::log_1(&format!("Failed to send touch event, {}", err).into());
+        }
+    });
+    for event_type in ["touchstart", "touchmove", "touchend", "touchcancel"] {
+        canvas.add_event_listener_with_callback(
+            event_type,
+            touch_event_closure.as_ref().unchecked_ref(),
+        )?;
+    }
+    touch_event_closure.forget();
+    let mut steering_touch = None;
+    let mut gamepad = GamepadPoller::new();
+
+    // Leaving the page (switching windows or hiding the tab) pauses the game, so the player
+    // doesn't lose lives while they're away
+    let (focus_sender, focus_receiver) = mpsc::sync_channel(10);
+    let focus_event_closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::Event| {
+        let hidden = web_sys::window()
+            .and_then(|window| window.document())
+            .is_some_and(|document| document.hidden());
+        let focused = match event.type_().as_str() {
+            "blur" => false,
+            "focus" => true,
+            _ => !hidden,
+        };
+        if let Err(err) = focus_sender.try_send(focused) {
+            console::log_1(&format!("Failed to send focus event, {}", err).into());
+        }
+    });
+    window
+        .add_event_listener_with_callback("blur", focus_event_closure.as_ref().unchecked_ref())?;
+    window
+        .add_event_listener_with_callback("focus", focus_event_closure.as_ref().unchecked_ref())?;
+    document.add_event_listener_with_callback(
+        "visibilitychange",
+        focus_event_closure.as_ref().unchecked_ref(),
+    )?;
+    focus_event_closure.forget();
+
+    let (time_scale_sender, time_scale_receiver) = mpsc::sync_channel(10);
+    TIME_SCALES.with(|senders| {
+        senders
+            .borrow_mut()
+            .insert(canvas_id.to_string(), time_scale_sender)
+    });
+
+    // Only the latest size matters, so one pending resize is as good as several
+    let (resize_sender, resize_receiver) = mpsc::sync_channel(1);
+    let resize_canvas = canvas.clone();
+    let resize_closure = Closure::<dyn FnMut()>::new(move || {
+        if fit {
+            fit_canvas(&resize_canvas, hidpi);
+        }
+        let _ = resize_sender.try_send(());
+    });
+    window.add_event_listener_with_callback("resize", resize_closure.as_ref().unchecked_ref())?;
+    resize_closure.forget();
 
     // The closure will need to be held onto and re-submitted for `request_animation_frame`
     // callbacks from within the body of the closure, so we need a reference-counted pointer that
@@ -63,44 +218,22 @@ pub fn start() {
     let animation_closure = Rc::new(RefCell::new(None));
     let animation_closure_initial = animation_closure.clone();
 
-    // Initialze game "globals" that the closure will take ownership over
-    let mut enemies = entities::Fleet::new(4, 6, MARGIN_Y, MARGIN_X, canvas_width - MARGIN_X);
-    let mut ship = entities::Ship::new(
-        0.5,
-        canvas_height - MARGIN_Y,
-        MARGIN_X,
-        canvas_width - MARGIN_X,
-    );
-    let mut last_ts = window.performance().unwrap().now();
-
     let closure_inner: Closure<dyn FnMut(TimeStamp)> = Closure::new(move |ts: TimeStamp| {
+        for input in gamepad.poll() {
+            game.send_input(input);
+        }
         match key_receiver.try_recv() {
             Ok(evt) => {
                 let evt_type = evt.type_();
                 console::log_1(
                     &format!("Key event: {} {} ({})", evt_type, evt.key(), evt.key_code()).into(),
                 );
-                match evt.key().as_str() {
-                    "a" | "ArrowLeft" => {
-                        ship.direction = if evt_type == "keydown" {
-                            entities::Direction::Left
-                        } else {
-                            entities::Direction::Stopped
-                        };
-                    }
-                    "d" | "ArrowRight" => {
-                        ship.direction = if evt_type == "keydown" {
-                            entities::Direction::Right
-                        } else {
-                            entities::Direction::Stopped
-                        };
-                    },
-                    "w" | "ArrowUp" => {
-                        if evt_type == "keydown" {
-                            ship.shoot();
-                        }
-                    },
-                    _ => {} // Ignore
+                let pressed = evt_type == "keydown";
+                // A held key keeps sending keydowns; only the first counts, so holding fire shoots
+                // once rather than at the auto-repeat rate
+                let input = game.key_input(&evt.key(), pressed);
+                if let (Some(input), false) = (input, evt.repeat()) {
+                    game.send_input(input);
                 }
             }
             Err(mpsc::TryRecvError::Empty) => {} // OK, no keys pressed
@@ -108,20 +241,91 @@ pub fn start() {
                 console::log_1(&format!("Failed to receive key event, {}", err).into());
             }
         }
-        context.clear_rect(0.0, 0.0, canvas_width, canvas_height);
-
-        let ts_offset = ts - last_ts;
-        last_ts = ts;
-        // TODO: consolidate game entities into one top-level struct that can have a single
-        // `.animate()` called
-        enemies.animate(&context, ts_offset);
-        ship.animate(&context, ts_offset);
+        // Several moves can arrive between frames, and only the last position matters
+        let mut mouse_x = None;
+        while let Ok(evt) = mouse_receiver.try_recv() {
+            let x = canvas_x(&pointer_canvas, evt.client_x());
+            if evt.type_() == "mousedown" {
+                // Fire from where the ship was clicked, not from wherever it was left
+                game.send_input(GameInput::MoveTo(game.game_x(x)));
+                game.send_input(GameInput::Fire);
+                mouse_x = None;
+            } else {
+                mouse_x = Some(x);
+            }
+        }
+        if let Some(x) = mouse_x {
+            game.send_input(GameInput::MoveTo(game.game_x(x)));
+        }
+        while let Ok(evt) = touch_receiver.try_recv() {
+            let touches = evt.changed_touches();
+            for touch in (0..touches.length()).filter_map(|i| touches.item(i)) {
+                let id = touch.identifier();
+                let x = canvas_x(&pointer_canvas, touch.client_x());
+                match evt.type_().as_str() {
+                    "touchstart" if x >= f64::from(pointer_canvas.width()) / 2.0 => {
+                        game.send_input(GameInput::Fire);
+                    }
+                    "touchstart" if steering_touch.is_none() => {
+                        steering_touch = Some(id);
+                        game.send_input(GameInput::SteerTo(Some(game.game_x(x))));
+                    }
+                    "touchmove" if steering_touch == Some(id) => {
+                        game.send_input(GameInput::SteerTo(Some(game.game_x(x))));
+                    }
+                    "touchend" | "touchcancel" if steering_touch == Some(id) => {
+                        steering_touch = None;
+                        game.send_input(GameInput::SteerTo(None));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if resize_receiver.try_recv().is_ok() {
+            game.resize();
+        }
+        while let Ok(focused) = focus_receiver.try_recv() {
+            game.set_focused(focused, ts);
+        }
+        while let Ok(scale) = time_scale_receiver.try_recv() {
+            game.set_time_scale(scale);
+        }
+        game.tick(ts);
 
         request_animation_frame(animation_closure.borrow().as_ref().unwrap());
     });
     *animation_closure_initial.borrow_mut() = Some(closure_inner);
 
     request_animation_frame(animation_closure_initial.borrow().as_ref().unwrap());
+    Ok(())
+}
+
+/// Size the canvas's drawing buffer to match the space it takes up on the page, in CSS pixels or,
+/// with `hidpi`, in the display's own pixels
+fn fit_canvas(canvas: &web_sys::HtmlCanvasElement, hidpi: bool) {
+    let ratio = if hidpi {
+        graphics::device_pixel_zoom()
+    } else {
+        1
+    };
+    let (width, height) = (canvas.client_width(), canvas.client_height());
+    if width > 0 && height > 0 {
+        canvas.set_width(width as u32 * ratio);
+        canvas.set_height(height as u32 * ratio);
+    }
+}
+
+/// Where a mouse or touch event's `client_x` falls along the canvas, in canvas pixels, allowing for
+/// where the canvas sits on the page and for its drawing buffer being a different size than it's
+/// shown at
+fn canvas_x(canvas: &web_sys::HtmlCanvasElement, client_x: i32) -> f64 {
+    let rect = canvas.get_bounding_client_rect();
+    let x = f64::from(client_x) - rect.left();
+    if rect.width() > 0.0 {
+        x * f64::from(canvas.width()) / rect.width()
+    } else {
+        x
+    }
 }
 
 fn request_animation_frame(f: &Closure<dyn FnMut(TimeStamp)>) {
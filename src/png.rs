@@ -0,0 +1,122 @@
+//! Decoding PNG images into RGBA pixels for sprites
+//!
+//! The `png` crate does the decoding; this only expands whatever color type the image uses to
+//! 8-bit RGBA, which is all sprites deal in.
+
+use ::png::{ColorType, Decoder, DecodingError, Transformations};
+
+/// A decoded image, as rows of RGBA pixels from top to bottom
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Image {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Decode the PNG image in `bytes` to RGBA
+pub(crate) fn decode(bytes: &[u8]) -> Result<Image, DecodingError> {
+    let mut decoder = Decoder::new(bytes);
+    // Palettes and transparency become RGB(A), and other bit depths 8-bit channels
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut pixels)?;
+    pixels.truncate(frame.buffer_size());
+
+    let data = match frame.color_type {
+        ColorType::Grayscale => pixels.iter().flat_map(|&v| [v, v, v, 0xff]).collect(),
+        ColorType::GrayscaleAlpha => pixels
+            .chunks(2)
+            .flat_map(|va| [va[0], va[0], va[0], va[1]])
+            .collect(),
+        ColorType::Rgb => pixels
+            .chunks(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+            .collect(),
+        // EXPAND leaves no indexed images
+        ColorType::Rgba | ColorType::Indexed => pixels,
+    };
+    Ok(Image {
+        width: frame.width,
+        height: frame.height,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAY: &[u8] = include_bytes!("png/gray.png");
+    const GRAY_ALPHA: &[u8] = include_bytes!("png/gray-alpha.png");
+    const RGB: &[u8] = include_bytes!("png/rgb.png");
+    const PALETTE: &[u8] = include_bytes!("png/palette.png");
+    /// 16x16, and split over three IDAT chunks around a tEXt one
+    const GRADIENT: &[u8] = include_bytes!("png/gradient.png");
+
+    #[test]
+    fn decodes_grayscale() {
+        let image = decode(GRAY).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(
+            image.data,
+            [
+                0, 0, 0, 255, 128, 128, 128, 255, 255, 255, 255, 255, //
+                10, 10, 10, 255, 20, 20, 20, 255, 30, 30, 30, 255,
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_grayscale_with_alpha() {
+        let image = decode(GRAY_ALPHA).unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(
+            image.data,
+            [0, 0, 0, 255, 100, 100, 100, 128, 200, 200, 200, 0, 255, 255, 255, 64]
+        );
+    }
+
+    #[test]
+    fn decodes_rgb_through_every_filter() {
+        let image = decode(RGB).unwrap();
+        assert_eq!((image.width, image.height), (4, 5));
+        let expected: Vec<u8> = (0..5u32)
+            .flat_map(|y| {
+                (0..4u32)
+                    .flat_map(move |x| [(x * 60) as u8, (y * 50) as u8, (x * y * 20) as u8, 255])
+            })
+            .collect();
+        assert_eq!(image.data, expected);
+    }
+
+    #[test]
+    fn decodes_palette_with_transparency() {
+        let image = decode(PALETTE).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(
+            image.data,
+            [
+                255, 0, 0, 0, 0, 255, 0, 128, 0, 0, 255, 255, //
+                255, 255, 255, 255, 0, 0, 255, 255, 0, 255, 0, 128,
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_across_chunks() {
+        let image = decode(GRADIENT).unwrap();
+        assert_eq!((image.width, image.height), (16, 16));
+        let expected: Vec<u8> = (0..16u8)
+            .flat_map(|y| (0..16u8).flat_map(move |x| [x * 16, y * 16, (x + y) * 8, 255 - x * y]))
+            .collect();
+        assert_eq!(image.data, expected);
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        assert!(decode(b"GIF89a\x01\x00\x01\x00").is_err());
+        assert!(decode(&[]).is_err());
+        assert!(decode(&GRAY[..GRAY.len() / 2]).is_err());
+    }
+}
@@ -0,0 +1,550 @@
+//! Recorded inputs for replaying a game, and snapshots of a game in progress, in a compact
+//! binary form that's small enough to put in a URL or `localStorage`
+//!
+//! Both encodings start with the same format version byte, so data saved by an older build can
+//! be recognized (and rejected) rather than misread. A replay is laid out as:
+//!
+//! | bytes    | contents                                                                |
+//! |----------|-------------------------------------------------------------------------|
+//! | 1        | format version, currently [`FORMAT_VERSION`]                            |
+//! | 8        | RNG seed, little-endian                                                 |
+//! | 1-10     | number of events, as a LEB128 varint                                    |
+//! | 9+ each  | per event: the input as one byte, for mouse moves and held touch        |
+//! |          | steering the x position as a little-endian `f64`, then its timestamp as |
+//! |          | a little-endian `f64`; both are stored whole so they replay bit-for-bit |
+//!
+//! and a snapshot as:
+//!
+//! | bytes    | contents                                                                |
+//! |----------|-------------------------------------------------------------------------|
+//! | 1        | format version, currently [`FORMAT_VERSION`]                            |
+//! | 12       | level, score, and lives left, each a little-endian `u32`                |
+//! | 24       | game time played, then the ship's x and y, each a little-endian `f64`   |
+//! | 1-10     | number of rows in the fleet, as a LEB128 varint                         |
+//! | 1+ each  | per row: its number of slots as a varint, then one bit per slot, set    |
+//! |          | for a live member, packed from the low bit up and padded to a byte      |
+
+use std::error::Error;
+use std::fmt;
+
+use crate::game::GameInput;
+use crate::geom::Distance;
+use crate::graphics::TimeStamp;
+
+pub const FORMAT_VERSION: u8 = 1;
+
+/// URL-safe base64 alphabet (RFC 4648 section 5); encoded replays are unpadded
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// One input sent to the game once it had played for `ts` of game time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayEvent {
+    pub ts: TimeStamp,
+    pub input: GameInput,
+}
+
+/// The seed a game was started with and every input it received, in order
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub events: Vec<ReplayEvent>,
+}
+
+/// Where a game in progress stands, enough to pick it back up from
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    /// The level being played, counting from 1
+    pub level: u32,
+    pub score: u32,
+    pub lives: u32,
+    /// Game time played so far
+    pub play_time: TimeStamp,
+    /// Where the ship's top left corner is
+    pub ship: (Distance, Distance),
+    /// Which of the fleet's slots still hold a live member, row by row from the top
+    pub fleet: Vec<Vec<bool>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// Encoded by a build using a different format version
+    Version(u8),
+    /// The data ends partway through
+    Truncated,
+    /// A byte that doesn't stand for any input
+    Input(u8),
+    /// Bytes left over after the last event
+    TrailingData,
+    /// A character outside the base64 alphabet, or an impossible length
+    Base64,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Version(version) => write!(
+                f,
+                "replay format version {} isn't supported, expected {}",
+                version, FORMAT_VERSION
+            ),
+            ReplayError::Truncated => write!(f, "replay data is cut short"),
+            ReplayError::Input(byte) => write!(f, "unknown input {:#04x} in replay", byte),
+            ReplayError::TrailingData => write!(f, "unexpected data after the end of the replay"),
+            ReplayError::Base64 => write!(f, "replay isn't valid base64"),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, ts: TimeStamp, input: GameInput) {
+        self.events.push(ReplayEvent { ts, input });
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + 8 + 10 + (self.events.len() * 9));
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        write_varint(&mut bytes, self.events.len() as u64);
+        for event in &self.events {
+            bytes.push(encode_input(event.input));
+            if let GameInput::MoveTo(x) | GameInput::SteerTo(Some(x)) = event.input {
+                bytes.extend_from_slice(&x.to_le_bytes());
+            }
+            bytes.extend_from_slice(&event.ts.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader(bytes);
+        let version = reader.byte()?;
+        if version != FORMAT_VERSION {
+            return Err(ReplayError::Version(version));
+        }
+        let seed = u64::from_le_bytes(reader.array()?);
+        let count = reader.varint()?;
+
+        // Every event takes at least 9 bytes, so a count the data can't hold is caught before
+        // allocating
+        if count > (reader.0.len() / 9) as u64 {
+            return Err(ReplayError::Truncated);
+        }
+        let mut events = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let input = match decode_input(reader.byte()?)? {
+                GameInput::MoveTo(_) => GameInput::MoveTo(f64::from_le_bytes(reader.array()?)),
+                GameInput::SteerTo(Some(_)) => {
+                    GameInput::SteerTo(Some(f64::from_le_bytes(reader.array()?)))
+                }
+                input => input,
+            };
+            let ts = f64::from_le_bytes(reader.array()?);
+            events.push(ReplayEvent { ts, input });
+        }
+        if !reader.0.is_empty() {
+            return Err(ReplayError::TrailingData);
+        }
+        Ok(Self { seed, events })
+    }
+
+    /// The binary encoding as URL-safe base64
+    pub fn to_base64(&self) -> String {
+        encode_base64(&self.to_bytes())
+    }
+
+    pub fn from_base64(text: &str) -> Result<Self, ReplayError> {
+        Self::from_bytes(&decode_base64(text)?)
+    }
+}
+
+impl Snapshot {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + 12 + 24 + 10 + (self.fleet.len() * 3));
+        bytes.push(FORMAT_VERSION);
+        for value in [self.level, self.score, self.lives] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [self.play_time, self.ship.0, self.ship.1] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        write_varint(&mut bytes, self.fleet.len() as u64);
+        for row in &self.fleet {
+            write_varint(&mut bytes, row.len() as u64);
+            for slots in row.chunks(8) {
+                let bits = slots
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |bits, (i, &live)| bits | (u8::from(live) << i));
+                bytes.push(bits);
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader(bytes);
+        let version = reader.byte()?;
+        if version != FORMAT_VERSION {
+            return Err(ReplayError::Version(version));
+        }
+        let level = u32::from_le_bytes(reader.array()?);
+        let score = u32::from_le_bytes(reader.array()?);
+        let lives = u32::from_le_bytes(reader.array()?);
+        let play_time = f64::from_le_bytes(reader.array()?);
+        let ship = (
+            f64::from_le_bytes(reader.array()?),
+            f64::from_le_bytes(reader.array()?),
+        );
+
+        // Every row takes at least a byte, and every 8 slots another, so counts the data can't
+        // hold are caught before allocating
+        let rows = reader.varint()?;
+        if rows > reader.0.len() as u64 {
+            return Err(ReplayError::Truncated);
+        }
+        let mut fleet = Vec::with_capacity(rows as usize);
+        for _ in 0..rows {
+            let slots = reader.varint()?;
+            if slots.div_ceil(8) > reader.0.len() as u64 {
+                return Err(ReplayError::Truncated);
+            }
+            let mut row = Vec::with_capacity(slots as usize);
+            while row.len() < slots as usize {
+                let bits = reader.byte()?;
+                let count = (slots as usize - row.len()).min(8);
+                row.extend((0..count).map(|i| bits & (1 << i) != 0));
+            }
+            fleet.push(row);
+        }
+        if !reader.0.is_empty() {
+            return Err(ReplayError::TrailingData);
+        }
+        Ok(Self {
+            level,
+            score,
+            lives,
+            play_time,
+            ship,
+            fleet,
+        })
+    }
+
+    /// The binary encoding as URL-safe base64
+    pub fn to_base64(&self) -> String {
+        encode_base64(&self.to_bytes())
+    }
+
+    pub fn from_base64(text: &str) -> Result<Self, ReplayError> {
+        Self::from_bytes(&decode_base64(text)?)
+    }
+}
+
+/// Pack an input into a byte: its kind in the low bits, and bit 7 set while a steering control
+/// is held
+fn encode_input(input: GameInput) -> u8 {
+    const HELD: u8 = 0x80;
+    let held = |held: bool| if held { HELD } else { 0 };
+    match input {
+        GameInput::Left(pressed) => held(pressed),
+        GameInput::Right(pressed) => 1 | held(pressed),
+        GameInput::Up(pressed) => 2 | held(pressed),
+        GameInput::Down(pressed) => 3 | held(pressed),
+        GameInput::Fire => 4,
+        GameInput::ToggleFiringLine => 5,
+        GameInput::Restart => 6,
+        GameInput::TogglePause => 7,
+        // Positions follow separately, see `Replay::to_bytes`
+        GameInput::MoveTo(_) => 8,
+        GameInput::SteerTo(target) => 9 | held(target.is_some()),
+        GameInput::ToggleFps => 10,
+    }
+}
+
+fn decode_input(byte: u8) -> Result<GameInput, ReplayError> {
+    let held = byte & 0x80 != 0;
+    match byte & 0x7f {
+        0 => Ok(GameInput::Left(held)),
+        1 => Ok(GameInput::Right(held)),
+        2 => Ok(GameInput::Up(held)),
+        3 => Ok(GameInput::Down(held)),
+        4 if !held => Ok(GameInput::Fire),
+        5 if !held => Ok(GameInput::ToggleFiringLine),
+        6 if !held => Ok(GameInput::Restart),
+        7 if !held => Ok(GameInput::TogglePause),
+        // The caller reads in the positions
+        8 if !held => Ok(GameInput::MoveTo(0.0)),
+        9 => Ok(GameInput::SteerTo(held.then_some(0.0))),
+        10 if !held => Ok(GameInput::ToggleFps),
+        _ => Err(ReplayError::Input(byte)),
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads from the front of a byte slice, failing with [`ReplayError::Truncated`] past its end
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, ReplayError> {
+        let (&byte, rest) = self.0.split_first().ok_or(ReplayError::Truncated)?;
+        self.0 = rest;
+        Ok(byte)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        if self.0.len() < N {
+            return Err(ReplayError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn varint(&mut self) -> Result<u64, ReplayError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReplayError::Truncated)
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | (u32::from(byte) << (16 - (8 * i)))
+        });
+        // Three bytes make four characters; a short final chunk makes one fewer per missing byte
+        for i in 0..=chunk.len() {
+            text.push(BASE64[((group >> (18 - (6 * i))) & 0x3f) as usize] as char);
+        }
+    }
+    text
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, ReplayError> {
+    let text = text.trim_end_matches('=').as_bytes();
+    if text.len() % 4 == 1 {
+        return Err(ReplayError::Base64);
+    }
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.chunks(4) {
+        let mut group = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = BASE64
+                .iter()
+                .position(|&b| b == c)
+                .ok_or(ReplayError::Base64)?;
+            group |= (value as u32) << (18 - (6 * i));
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - (8 * i))) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A replay with every kind of input, including the ones that carry positions
+    fn sample() -> Replay {
+        let mut replay = Replay::new(0x0123_4567_89ab_cdef);
+        let inputs = [
+            GameInput::Left(true),
+            GameInput::Left(false),
+            GameInput::Right(true),
+            GameInput::Up(true),
+            GameInput::Down(false),
+            GameInput::Fire,
+            GameInput::ToggleFiringLine,
+            GameInput::Restart,
+            GameInput::TogglePause,
+            GameInput::MoveTo(123.456),
+            GameInput::SteerTo(Some(-0.1)),
+            GameInput::SteerTo(None),
+            GameInput::ToggleFps,
+        ];
+        for (i, input) in inputs.into_iter().enumerate() {
+            replay.record(1000.0 + (i as f64 * 16.666), input);
+        }
+        replay
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let replay = sample();
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()), Ok(replay));
+        assert_eq!(
+            Replay::from_bytes(&Replay::new(7).to_bytes()),
+            Ok(Replay::new(7))
+        );
+    }
+
+    #[test]
+    fn round_trips_through_base64() {
+        let replay = sample();
+        let text = replay.to_base64();
+        assert!(text.bytes().all(|c| BASE64.contains(&c)), "{}", text);
+        assert_eq!(Replay::from_base64(&text), Ok(replay));
+        // Padding from other encoders is accepted
+        let short = Replay::new(1);
+        assert_eq!(
+            Replay::from_base64(&format!("{}==", short.to_base64())),
+            Ok(short)
+        );
+    }
+
+    #[test]
+    fn long_replays_round_trip() {
+        // Enough events that the count takes more than one varint byte
+        let mut replay = Replay::new(u64::MAX);
+        for i in 0..300 {
+            replay.record(f64::from(i), GameInput::Right(i % 2 == 0));
+        }
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()), Ok(replay));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut bytes = sample().to_bytes();
+        bytes[0] = FORMAT_VERSION + 1;
+        assert_eq!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::Version(FORMAT_VERSION + 1))
+        );
+        bytes[0] = 0;
+        assert_eq!(Replay::from_bytes(&bytes), Err(ReplayError::Version(0)));
+    }
+
+    #[test]
+    fn rejects_every_truncation() {
+        let bytes = sample().to_bytes();
+        for length in 0..bytes.len() {
+            assert_eq!(
+                Replay::from_bytes(&bytes[..length]),
+                Err(ReplayError::Truncated),
+                "{} of {} bytes",
+                length,
+                bytes.len()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_counts_the_data_cant_hold() {
+        let mut bytes = vec![FORMAT_VERSION];
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        write_varint(&mut bytes, u64::MAX);
+        assert_eq!(Replay::from_bytes(&bytes), Err(ReplayError::Truncated));
+    }
+
+    #[test]
+    fn rejects_trailing_data() {
+        let mut bytes = sample().to_bytes();
+        bytes.push(0);
+        assert_eq!(Replay::from_bytes(&bytes), Err(ReplayError::TrailingData));
+    }
+
+    #[test]
+    fn rejects_unknown_inputs() {
+        let mut replay = Replay::new(0);
+        replay.record(0.0, GameInput::Fire);
+        let mut bytes = replay.to_bytes();
+        let input = bytes.len() - 9;
+        for byte in [0x0b, 0x7f, 0x84] {
+            bytes[input] = byte;
+            assert_eq!(Replay::from_bytes(&bytes), Err(ReplayError::Input(byte)));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_base64() {
+        assert_eq!(Replay::from_base64("AAAA+AAA"), Err(ReplayError::Base64));
+        assert_eq!(Replay::from_base64("AAAAA"), Err(ReplayError::Base64));
+    }
+
+    /// A snapshot partway through a fleet with gaps, and a row long enough to take two bytes
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            level: 3,
+            score: 1250,
+            lives: 2,
+            play_time: 45_678.9,
+            ship: (301.5, 416.0),
+            fleet: vec![
+                vec![true, false, true, true, false],
+                vec![],
+                (0..11).map(|i| i % 3 != 0).collect(),
+            ],
+        }
+    }
+
+    #[test]
+    fn snapshots_round_trip() {
+        let snapshot = snapshot();
+        assert_eq!(
+            Snapshot::from_bytes(&snapshot.to_bytes()),
+            Ok(snapshot.clone())
+        );
+        assert_eq!(Snapshot::from_base64(&snapshot.to_base64()), Ok(snapshot));
+        let empty = Snapshot::default();
+        assert_eq!(Snapshot::from_bytes(&empty.to_bytes()), Ok(empty));
+    }
+
+    #[test]
+    fn snapshots_pack_a_bit_per_slot() {
+        // Version, three u32s, three f64s, the row count, then a count and bits for each row
+        let bytes = snapshot().to_bytes();
+        assert_eq!(bytes.len(), 1 + 12 + 24 + 1 + (1 + 1) + 1 + (1 + 2));
+        assert_eq!(bytes[37..], [3, 5, 0b01101, 0, 11, 0b10110110, 0b101]);
+    }
+
+    #[test]
+    fn snapshots_share_the_version_byte() {
+        let mut bytes = snapshot().to_bytes();
+        assert_eq!(bytes[0], FORMAT_VERSION);
+        bytes[0] = FORMAT_VERSION + 1;
+        assert_eq!(
+            Snapshot::from_bytes(&bytes),
+            Err(ReplayError::Version(FORMAT_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn snapshots_reject_every_truncation_and_trailing_data() {
+        let mut bytes = snapshot().to_bytes();
+        for length in 0..bytes.len() {
+            assert_eq!(
+                Snapshot::from_bytes(&bytes[..length]),
+                Err(ReplayError::Truncated),
+                "{} of {} bytes",
+                length,
+                bytes.len()
+            );
+        }
+        bytes.push(0);
+        assert_eq!(Snapshot::from_bytes(&bytes), Err(ReplayError::TrailingData));
+    }
+}
@@ -0,0 +1,37 @@
+/// Small, fast, seedable pseudo-random number generator (xorshift64*)
+///
+/// Not suitable for anything security-related, but the same seed always produces the same
+/// sequence, which makes randomized gameplay reproducible.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck at zero, so nudge a zero seed onto a usable state
+        Self(if seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            seed
+        })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill an f64's mantissa exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A number in `[min, max)`
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (self.next_f64() * (max - min))
+    }
+}
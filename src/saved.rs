@@ -0,0 +1,67 @@
+//! The last finished game's replay and the last paused game's snapshot, kept in the browser's
+//! `localStorage` for `data-replay` and `data-resume` to pick back up
+//!
+//! As with the high score, storage can be missing or refuse access, in which case nothing is
+//! kept. Anything stored that no longer decodes, say from an older build, is ignored.
+
+use web_sys::{console, Storage};
+
+use crate::replay::{Replay, Snapshot};
+
+#[derive(Default)]
+pub(crate) struct Saved {
+    storage: Option<Storage>,
+}
+
+impl Saved {
+    const REPLAY: &'static str = "canvas-invaders.replay";
+    const SNAPSHOT: &'static str = "canvas-invaders.snapshot";
+
+    pub(crate) fn load() -> Self {
+        let storage = web_sys::window().and_then(|window| window.local_storage().ok().flatten());
+        Self { storage }
+    }
+
+    /// The replay of the last game played to the end, if one is stored
+    pub(crate) fn replay(&self) -> Option<Replay> {
+        let stored = self.get(Self::REPLAY)?;
+        Replay::from_base64(&stored)
+            .map_err(|err| console::log_1(&format!("Ignoring the saved replay, {}", err).into()))
+            .ok()
+    }
+
+    pub(crate) fn store_replay(&self, replay: &Replay) {
+        self.set(Self::REPLAY, &replay.to_base64());
+    }
+
+    /// Where the last game paused before it ended stood, if one is stored
+    pub(crate) fn snapshot(&self) -> Option<Snapshot> {
+        let stored = self.get(Self::SNAPSHOT)?;
+        Snapshot::from_base64(&stored)
+            .map_err(|err| console::log_1(&format!("Ignoring the saved game, {}", err).into()))
+            .ok()
+    }
+
+    pub(crate) fn store_snapshot(&self, snapshot: &Snapshot) {
+        self.set(Self::SNAPSHOT, &snapshot.to_base64());
+    }
+
+    /// Forget the stored snapshot, once the game it was taken from is over
+    pub(crate) fn clear_snapshot(&self) {
+        if let Some(storage) = &self.storage {
+            let _ = storage.remove_item(Self::SNAPSHOT);
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.storage.as_ref()?.get_item(key).ok().flatten()
+    }
+
+    fn set(&self, key: &str, value: &str) {
+        if let Some(storage) = &self.storage {
+            if let Err(err) = storage.set_item(key, value) {
+                console::log_1(&format!("Failed to save {}: {:?}", key, err).into());
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! The player's running score and its readout in the corner of the play area
+
+use web_sys::CanvasRenderingContext2d;
+
+use crate::geom::{Distance, Position, XY};
+use crate::graphics::Draw;
+
+pub(crate) struct Score {
+    pub(crate) points: u32,
+    /// A best score to show alongside, if any
+    pub(crate) high: Option<u32>,
+    /// Where the text's left end sits on its baseline
+    pub(crate) position: Position,
+    /// Text height in pixels
+    pub(crate) font_size: Distance,
+    /// Any CSS color
+    pub(crate) color: String,
+}
+
+impl Score {
+    pub(crate) const FONT_SIZE: Distance = 16.0;
+    pub(crate) const COLOR: &'static str = "gray";
+
+    /// A score of zero, drawn with its text starting at `position`
+    pub(crate) fn new(position: Position) -> Self {
+        Self {
+            points: 0,
+            high: None,
+            position,
+            font_size: Self::FONT_SIZE,
+            color: Self::COLOR.to_string(),
+        }
+    }
+
+    /// Add `points`, stopping at the largest score that can be shown rather than wrapping around
+    pub(crate) fn add(&mut self, points: u32) {
+        self.points = self.points.saturating_add(points);
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.points = 0;
+    }
+}
+
+impl Draw for Score {
+    fn draw(&mut self, context: &CanvasRenderingContext2d) {
+        context.set_font(&format!("{}px monospace", self.font_size));
+        context.set_fill_style_str(&self.color);
+        context.set_text_align("left");
+        let text = match self.high {
+            Some(high) => format!("SCORE: {}  HIGH: {}", self.points, high),
+            None => format!("SCORE: {}", self.points),
+        };
+        context
+            .fill_text(&text, self.position.x(), self.position.y())
+            .expect("fill_text");
+    }
+}